@@ -0,0 +1,131 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Pitch of the buzzer, in Hz, when none is configured. The original hardware just had a fixed
+/// tone, so any pitch is as faithful as another.
+pub const DEFAULT_BUZZER_FREQUENCY: f32 = 440.;
+
+/// Peak amplitude of the square wave. Square waves are harsh at full volume.
+const VOLUME: f32 = 0.2;
+
+/// Square wave generator, producing one sample at a time
+#[derive(Debug, Clone)]
+pub struct SquareWave {
+    /// How far through the current cycle we are, from 0 to 1
+    phase: f32,
+    /// How far each sample moves through a cycle
+    step: f32,
+}
+
+impl SquareWave {
+    pub fn new(frequency: f32, sample_rate: f32) -> SquareWave {
+        SquareWave {
+            phase: 0.,
+            step: frequency / sample_rate,
+        }
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let sample = if self.phase < 0.5 { VOLUME } else { -VOLUME };
+        self.phase = (self.phase + self.step).fract();
+        sample
+    }
+}
+
+/// Plays a tone on the default audio output while switched on. The output stream keeps running
+/// the whole time and plays silence while off, so switching is just flipping a flag.
+///
+/// Only available with the `audio` feature. Without it, [`Buzzer::new`] always fails.
+pub struct Buzzer {
+    on: Arc<AtomicBool>,
+    #[cfg(feature = "audio")]
+    _stream: cpal::Stream,
+}
+
+impl Buzzer {
+    /// Opens the default audio output to play a square wave at `frequency` Hz
+    #[cfg(feature = "audio")]
+    pub fn new(frequency: f32) -> anyhow::Result<Buzzer> {
+        use anyhow::Context;
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .context("there is no audio output device")?;
+        let config = device
+            .default_output_config()
+            .context("could not get the audio output config")?;
+        let wave = SquareWave::new(frequency, config.sample_rate().0 as f32);
+        let on: Arc<AtomicBool> = Arc::default();
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => Self::build_stream::<f32>(&device, &config, wave, &on),
+            cpal::SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, wave, &on),
+            cpal::SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, wave, &on),
+            format => anyhow::bail!("unsupported audio sample format {format}"),
+        }?;
+        stream.play().context("could not start audio output")?;
+        Ok(Buzzer {
+            on,
+            _stream: stream,
+        })
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn new(_frequency: f32) -> anyhow::Result<Buzzer> {
+        anyhow::bail!("built without the audio feature")
+    }
+
+    #[cfg(feature = "audio")]
+    fn build_stream<T: cpal::SizedSample + cpal::FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+        mut wave: SquareWave,
+        on: &Arc<AtomicBool>,
+    ) -> anyhow::Result<cpal::Stream> {
+        use anyhow::Context;
+        use cpal::traits::DeviceTrait;
+
+        let channels = usize::from(config.channels());
+        let on = on.clone();
+        device
+            .build_output_stream(
+                &config.config(),
+                move |data: &mut [T], _| {
+                    let on = on.load(Ordering::Relaxed);
+                    for frame in data.chunks_mut(channels) {
+                        let sample = if on {
+                            T::from_sample(wave.next_sample())
+                        } else {
+                            T::EQUILIBRIUM
+                        };
+                        frame.fill(sample);
+                    }
+                },
+                |e| log::error!("audio output failed: {e}"),
+                None,
+            )
+            .context("could not open the audio output stream")
+    }
+
+    pub fn set_on(&self, on: bool) {
+        self.on.store(on, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SquareWave, VOLUME};
+
+    #[test]
+    fn test_square_wave() {
+        let mut wave = SquareWave::new(1000., 8000.);
+        let samples: Vec<f32> = (0..16).map(|_| wave.next_sample()).collect();
+        let cycle = [
+            VOLUME, VOLUME, VOLUME, VOLUME, -VOLUME, -VOLUME, -VOLUME, -VOLUME,
+        ];
+        assert_eq!(samples[..8], cycle);
+        assert_eq!(samples[8..], cycle);
+    }
+}
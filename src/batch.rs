@@ -0,0 +1,79 @@
+use crate::{
+    config::DEFAULT_SPEED,
+    emulator::{Chip8State, EmulatedChip8, KeyInput, Result},
+    font::Chip8Font,
+    program::Program,
+};
+use rayon::prelude::*;
+use std::time::Duration;
+
+/// Runs one emulator per ROM across a thread pool, for `steps` instructions each with no keys
+/// pressed, and returns their final states in the same order as `roms`. Every instance gets its
+/// own random seed derived from `seed` (`seed + index`), so the whole batch is reproducible.
+/// Instances that hit an error stop there and return it instead.
+pub fn run_batch(roms: Vec<Program>, steps: usize, seed: u64) -> Vec<Result<Chip8State>> {
+    let font = Chip8Font::new_from_default().expect("default font should always be valid");
+    let period = Duration::from_secs_f64(1. / DEFAULT_SPEED);
+
+    roms.into_par_iter()
+        .enumerate()
+        .map(|(idx, program)| {
+            let mut chip = EmulatedChip8::new().with_seed(seed.wrapping_add(idx as u64));
+            chip.write_font(&font);
+            chip.load_program(&program);
+            for _ in 0..steps {
+                chip.step(KeyInput::default(), period)?;
+            }
+            Ok(chip.get_state().clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::run_batch;
+    use crate::{
+        emulator::{EmulatedChip8, Register},
+        program::Program,
+    };
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_emulator_is_send() {
+        assert_send::<EmulatedChip8>();
+    }
+
+    #[test]
+    fn test_run_batch() {
+        // Each ROM adds a different amount to V0 in a loop: ADD V0, n; JP 0x200
+        let roms: Vec<Program> = (1..=8)
+            .map(|n| Program::new_from_data(&[0x70, n, 0x12, 0x00]).unwrap())
+            .collect();
+        let states = run_batch(roms, 20, 0);
+        assert_eq!(states.len(), 8);
+        for (n, state) in (1..=8).zip(states) {
+            // 20 steps is 10 trips around the loop
+            assert_eq!(state.unwrap().gp_registers[0], Register(n * 10));
+        }
+    }
+
+    #[test]
+    fn test_run_batch_seeded() {
+        // RND V0, 0xFF; RND V1, 0xFF
+        let roms = || {
+            (0..2)
+                .map(|_| Program::new_from_data(&[0xC0, 0xFF, 0xC1, 0xFF]).unwrap())
+                .collect()
+        };
+        let registers = |seed| -> Vec<[Register; 16]> {
+            run_batch(roms(), 2, seed)
+                .into_iter()
+                .map(|state| state.unwrap().gp_registers)
+                .collect()
+        };
+        assert_eq!(registers(7), registers(7));
+        // Each instance gets a different seed
+        assert_ne!(registers(7)[0], registers(7)[1]);
+    }
+}
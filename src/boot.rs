@@ -0,0 +1,95 @@
+use crate::{
+    display::{Coordinates, Display, DrawMode, SCREEN_RES},
+    emulator::KeyInput,
+    font::Chip8Font,
+};
+use std::time::Duration;
+
+/// A short pause before the ROM starts, showing a "C8" splash drawn with the built-in font. Some
+/// original interpreters had a startup delay like this; here it's purely cosmetic. Pressing any
+/// key skips it.
+#[derive(Debug, Clone)]
+pub struct BootSplash {
+    remaining: Duration,
+    display: Display,
+}
+
+impl BootSplash {
+    /// Characters drawn on the splash screen, as font digits
+    const TEXT: [u8; 2] = [0xC, 0x8];
+    /// Horizontal distance between the start of each character, in pixels
+    const CHAR_SPACING: u8 = 6;
+
+    /// # Arguments
+    /// * `duration` - How long to show the splash for, unless skipped
+    /// * `font` - Font the splash text gets drawn with
+    pub fn new(duration: Duration, font: &Chip8Font) -> BootSplash {
+        let mut display = Display::default();
+        let text_width = Self::CHAR_SPACING * (Self::TEXT.len() as u8 - 1) + 4;
+        let start = Coordinates::new(
+            (SCREEN_RES.width as u8 - text_width) / 2,
+            (SCREEN_RES.height as u8 - 5) / 2,
+        );
+        for (idx, digit) in Self::TEXT.iter().enumerate() {
+            let offset = Coordinates::new(Self::CHAR_SPACING * idx as u8, 0);
+            display.apply_sprite(font.glyph(*digit), start + offset, DrawMode::Xor);
+        }
+
+        BootSplash {
+            remaining: duration,
+            display,
+        }
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    pub fn finished(&self) -> bool {
+        self.remaining.is_zero()
+    }
+
+    /// Counts down the time left on the splash, ending it straight away if any key is pressed.
+    /// Returns whether the splash is over.
+    pub fn advance(&mut self, elapsed: Duration, key_input: &KeyInput) -> bool {
+        if key_input.first_pressed().is_some() {
+            self.remaining = Duration::ZERO;
+        } else {
+            self.remaining = self.remaining.saturating_sub(elapsed);
+        }
+        self.finished()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BootSplash;
+    use crate::{emulator::KeyInput, font::Chip8Font};
+    use std::time::Duration;
+
+    fn splash(duration: Duration) -> BootSplash {
+        BootSplash::new(duration, &Chip8Font::new_from_default().unwrap())
+    }
+
+    #[test]
+    fn test_splash_duration() {
+        let mut splash = splash(Duration::from_millis(100));
+        assert!(!splash.advance(Duration::from_millis(60), &KeyInput::default()));
+        assert!(!splash.advance(Duration::from_millis(39), &KeyInput::default()));
+        assert!(splash.advance(Duration::from_millis(1), &KeyInput::default()));
+    }
+
+    #[test]
+    fn test_splash_skipped_by_key() {
+        let mut splash = splash(Duration::from_secs(10));
+        let mut key_input = KeyInput::default();
+        key_input.key_state[0x5] = true;
+        assert!(splash.advance(Duration::from_millis(1), &key_input));
+    }
+
+    #[test]
+    fn test_splash_draws_text() {
+        let splash = splash(Duration::from_secs(1));
+        assert!(splash.display().rows().flatten().any(|pixel| *pixel));
+    }
+}
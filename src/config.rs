@@ -0,0 +1,184 @@
+use crate::{
+    quirks::{MemoryIncrementQuirk, ReservedExecution, ShiftFlagOrder, ShiftQuirk},
+    renderer::{Aspect, GlyphSet, PalettePreset},
+};
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Default instruction rate, in Hz. 700 instructions/second is a rough average of real timing
+pub const DEFAULT_SPEED: f64 = 700.;
+
+/// Emulator options, as read from a TOML config file. Every field is optional so that a config
+/// file only needs to set what it cares about, and so that it can be layered with the options
+/// passed on the command line (see [`Config::or`]).
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Config {
+    pub speed: Option<f64>,
+    pub speed_ramp_ms: Option<u64>,
+    pub save_dir: Option<PathBuf>,
+    pub skip_unknown_opcodes: Option<bool>,
+    pub warn_on_blank_sprite: Option<bool>,
+    pub warn_on_stack_imbalance: Option<bool>,
+    pub ascii: Option<bool>,
+    pub glyphs: Option<GlyphSet>,
+    pub aspect: Option<Aspect>,
+    pub palette: Option<PalettePreset>,
+    pub ghosting: Option<bool>,
+    pub debug_panel: Option<bool>,
+    pub buzzer_frequency: Option<f32>,
+    pub keymap: Option<PathBuf>,
+    pub shift_quirk: Option<ShiftQuirk>,
+    pub shift_flag_order: Option<ShiftFlagOrder>,
+    pub memory_increment: Option<MemoryIncrementQuirk>,
+    pub strict_keys: Option<bool>,
+    pub cls_resets_vf: Option<bool>,
+    pub index_wraps: Option<bool>,
+    pub clipped_rows_collide: Option<bool>,
+    pub drw_start_clips: Option<bool>,
+    pub reserved_execution: Option<ReservedExecution>,
+    pub one_draw_per_frame: Option<bool>,
+    pub boot_delay_ms: Option<u64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not read the config file: {0}")]
+    CouldNotRead(#[source] std::io::Error),
+    #[error("invalid config file: {0}")]
+    InvalidConfig(#[source] toml::de::Error),
+    #[cfg(not(feature = "serde"))]
+    #[error("built without the serde feature, so config files can't be read")]
+    Unsupported,
+}
+
+impl Config {
+    #[cfg(feature = "serde")]
+    pub fn new_from_str(config: &str) -> Result<Config, Error> {
+        toml::from_str(config).map_err(Error::InvalidConfig)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn new_from_str(_config: &str) -> Result<Config, Error> {
+        Err(Error::Unsupported)
+    }
+
+    pub fn new_from_file<P: AsRef<Path>>(path: P) -> Result<Config, Error> {
+        let config = std::fs::read_to_string(path).map_err(Error::CouldNotRead)?;
+        Self::new_from_str(&config)
+    }
+
+    /// Combines two configs, with any values set in `self` taking precedence over `fallback`.
+    /// This is how command line options override the config file.
+    pub fn or(self, fallback: Config) -> Config {
+        Config {
+            speed: self.speed.or(fallback.speed),
+            speed_ramp_ms: self.speed_ramp_ms.or(fallback.speed_ramp_ms),
+            save_dir: self.save_dir.or(fallback.save_dir),
+            skip_unknown_opcodes: self.skip_unknown_opcodes.or(fallback.skip_unknown_opcodes),
+            warn_on_blank_sprite: self.warn_on_blank_sprite.or(fallback.warn_on_blank_sprite),
+            warn_on_stack_imbalance: self
+                .warn_on_stack_imbalance
+                .or(fallback.warn_on_stack_imbalance),
+            ascii: self.ascii.or(fallback.ascii),
+            glyphs: self.glyphs.or(fallback.glyphs),
+            aspect: self.aspect.or(fallback.aspect),
+            palette: self.palette.or(fallback.palette),
+            ghosting: self.ghosting.or(fallback.ghosting),
+            debug_panel: self.debug_panel.or(fallback.debug_panel),
+            buzzer_frequency: self.buzzer_frequency.or(fallback.buzzer_frequency),
+            keymap: self.keymap.or(fallback.keymap),
+            shift_quirk: self.shift_quirk.or(fallback.shift_quirk),
+            shift_flag_order: self.shift_flag_order.or(fallback.shift_flag_order),
+            memory_increment: self.memory_increment.or(fallback.memory_increment),
+            strict_keys: self.strict_keys.or(fallback.strict_keys),
+            cls_resets_vf: self.cls_resets_vf.or(fallback.cls_resets_vf),
+            index_wraps: self.index_wraps.or(fallback.index_wraps),
+            clipped_rows_collide: self.clipped_rows_collide.or(fallback.clipped_rows_collide),
+            drw_start_clips: self.drw_start_clips.or(fallback.drw_start_clips),
+            reserved_execution: self.reserved_execution.or(fallback.reserved_execution),
+            one_draw_per_frame: self.one_draw_per_frame.or(fallback.one_draw_per_frame),
+            boot_delay_ms: self.boot_delay_ms.or(fallback.boot_delay_ms),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use super::{Config, Error};
+    use crate::quirks::ShiftFlagOrder;
+    use std::path::PathBuf;
+
+    const SAMPLE_CONFIG: &str = r#"
+        speed = 1000.0
+        save_dir = "saves"
+        warn_on_blank_sprite = true
+        shift_flag_order = "result-last"
+    "#;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::new_from_str(SAMPLE_CONFIG).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                speed: Some(1000.),
+                save_dir: Some(PathBuf::from("saves")),
+                warn_on_blank_sprite: Some(true),
+                shift_flag_order: Some(ShiftFlagOrder::ResultLast),
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_config_unknown_option() {
+        assert!(matches!(
+            Config::new_from_str("sped = 10.0"),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let file_config = Config::new_from_str(SAMPLE_CONFIG).unwrap();
+        let cli_config = Config {
+            speed: Some(350.),
+            speed_ramp_ms: Some(100),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            cli_config.or(file_config),
+            Config {
+                speed: Some(350.),
+                speed_ramp_ms: Some(100),
+                save_dir: Some(PathBuf::from("saves")),
+                skip_unknown_opcodes: None,
+                warn_on_blank_sprite: Some(true),
+                warn_on_stack_imbalance: None,
+                ascii: None,
+                glyphs: None,
+                aspect: None,
+                palette: None,
+                ghosting: None,
+                debug_panel: None,
+                buzzer_frequency: None,
+                keymap: None,
+                shift_quirk: None,
+                shift_flag_order: Some(ShiftFlagOrder::ResultLast),
+                memory_increment: None,
+                strict_keys: None,
+                cls_resets_vf: None,
+                index_wraps: None,
+                clipped_rows_collide: None,
+                drw_start_clips: None,
+                reserved_execution: None,
+                one_draw_per_frame: None,
+                boot_delay_ms: None,
+            }
+        );
+    }
+}
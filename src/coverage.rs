@@ -0,0 +1,215 @@
+use crate::{
+    emulator::Address,
+    opcodes::{self, DecodeTable, OpCodeReader},
+    program::{Program, PROGRAM_START},
+};
+use byteorder::{BigEndian, ByteOrder};
+use std::{collections::BTreeMap, fmt};
+
+/// Records which addresses got executed and which kinds of opcode ran over the course of a run.
+/// This lets test ROM authors check their ROM actually exercises what it's meant to test.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// How many times the instruction at each address got executed
+    executions: BTreeMap<Address, u64>,
+    /// How many times each [`OpCodeReader`] ran, by name
+    opcodes: BTreeMap<String, u64>,
+}
+
+impl Coverage {
+    pub fn record(&mut self, address: Address, instruction: &dyn OpCodeReader) {
+        *self.executions.entry(address).or_default() += 1;
+        *self.opcodes.entry(reader_name(instruction)).or_default() += 1;
+    }
+
+    /// Every address an instruction got executed from, in order
+    pub fn addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.executions.keys().copied()
+    }
+
+    /// How many times the instruction at `address` got executed
+    pub fn execution_count(&self, address: Address) -> u64 {
+        self.executions.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Total number of instructions executed
+    pub fn instruction_count(&self) -> u64 {
+        self.executions.values().sum()
+    }
+
+    /// How many times each kind of opcode ran, keyed by the name of its [`OpCodeReader`]
+    pub fn opcodes(&self) -> &BTreeMap<String, u64> {
+        &self.opcodes
+    }
+
+    /// Groups the executed addresses into runs of consecutive instructions, returned as
+    /// inclusive `(first, last)` address pairs.
+    pub fn reached_ranges(&self) -> Vec<(Address, Address)> {
+        let mut ranges: Vec<(Address, Address)> = Vec::new();
+        for address in self.addresses() {
+            match ranges.last_mut() {
+                Some((_, last)) if last.0 + 2 == address.0 => *last = address,
+                _ => ranges.push((address, address)),
+            }
+        }
+        ranges
+    }
+
+    /// Returns a printable summary of how much of `program` got covered
+    pub fn report<'a>(&'a self, program: &'a Program) -> CoverageReport<'a> {
+        CoverageReport {
+            coverage: self,
+            program,
+        }
+    }
+
+    /// Returns a listing of `program`, with how many times each instruction got executed
+    pub fn listing<'a>(&'a self, program: &'a Program) -> ProfileListing<'a> {
+        ProfileListing {
+            coverage: self,
+            program,
+        }
+    }
+}
+
+/// Summary of a [`Coverage`] against the program it was recorded from
+pub struct CoverageReport<'a> {
+    coverage: &'a Coverage,
+    program: &'a Program,
+}
+
+impl fmt::Display for CoverageReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = PROGRAM_START.0;
+        let end = start + self.program.data().len() as u16;
+        let total_words = self.program.data().len().div_ceil(2);
+        let reached_words = self
+            .coverage
+            .addresses()
+            .filter(|address| (start..end).contains(&address.0))
+            .count();
+        writeln!(
+            f,
+            "reached {reached_words} of {total_words} instruction words in the ROM"
+        )?;
+        for (first, last) in self.coverage.reached_ranges() {
+            writeln!(f, "  {first}-{last}")?;
+        }
+
+        writeln!(f, "exercised opcodes:")?;
+        for opcode in self.coverage.opcodes().keys() {
+            writeln!(f, "  {opcode}")?;
+        }
+        writeln!(f, "never exercised:")?;
+        for instruction in opcodes::supported_instructions() {
+            let name = reader_name(instruction.as_ref());
+            if !self.coverage.opcodes().contains_key(&name) {
+                writeln!(f, "  {name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Listing of a program with per-instruction execution counts, for finding hot loops and dead
+/// code. Every word of the ROM is listed as if it were an instruction, so sprite data shows up as
+/// (never executed) instructions too.
+pub struct ProfileListing<'a> {
+    coverage: &'a Coverage,
+    program: &'a Program,
+}
+
+impl fmt::Display for ProfileListing<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decode_table = DecodeTable::shared();
+        writeln!(f, "address  opcode       count  instruction")?;
+        for (idx, word) in self.program.data().chunks(2).enumerate() {
+            let address = Address(PROGRAM_START.0 + 2 * idx as u16);
+            // A trailing odd byte gets listed as the high byte of an instruction
+            let opcode = if word.len() == 2 {
+                BigEndian::read_u16(word)
+            } else {
+                u16::from(word[0]) << 8
+            };
+            let name = decode_table
+                .lookup(opcode)
+                .map(reader_name)
+                .unwrap_or_else(|| "-".to_string());
+            let count = self.coverage.execution_count(address);
+            writeln!(f, "{address}   {opcode:04x}  {count:>10}  {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Opcode readers are unit structs, so their debug output is just the name
+fn reader_name(instruction: &dyn OpCodeReader) -> String {
+    format!("{instruction:?}")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emulator::{Address, EmulatedChip8, KeyInput},
+        program::Program,
+    };
+    use expect_test::expect;
+    use std::time::Duration;
+
+    #[test]
+    fn test_coverage_small_loop() {
+        // LD V0, 0x00; ADD V0, 0x01; SE V0, 0x03; JP 0x202; JP 0x208
+        let program =
+            Program::new_from_data(&[0x60, 0x00, 0x70, 0x01, 0x30, 0x03, 0x12, 0x02, 0x12, 0x08])
+                .unwrap();
+        let mut chip = EmulatedChip8::new().with_coverage(true);
+        chip.load_program(&program);
+        for _ in 0..20 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+
+        let coverage = chip.coverage().unwrap();
+        assert_eq!(
+            coverage.addresses().collect::<Vec<_>>(),
+            (0x200..=0x208).step_by(2).map(Address).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            coverage.reached_ranges(),
+            vec![(Address(0x200), Address(0x208))]
+        );
+        assert_eq!(
+            coverage.opcodes().keys().collect::<Vec<_>>(),
+            vec![
+                "AddRegisterConst",
+                "Jump",
+                "SetRegisterConst",
+                "SkipConstEqual"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_listing() {
+        // LD V0, 0x00; ADD V0, 0x01; SE V0, 0x02; JP 0x202; JP 0x208
+        let program =
+            Program::new_from_data(&[0x60, 0x00, 0x70, 0x01, 0x30, 0x02, 0x12, 0x02, 0x12, 0x08])
+                .unwrap();
+        let mut chip = EmulatedChip8::new().with_coverage(true);
+        chip.load_program(&program);
+        for _ in 0..10 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+
+        // The loop body runs twice before the skip breaks out of it
+        let listing = chip.coverage().unwrap().listing(&program).to_string();
+        expect![[r#"
+            address  opcode       count  instruction
+            0x0200   6000           1  SetRegisterConst
+            0x0202   7001           2  AddRegisterConst
+            0x0204   3002           2  SkipConstEqual
+            0x0206   1202           1  Jump
+            0x0208   1208           4  Jump
+        "#]]
+        .assert_eq(&listing);
+    }
+}
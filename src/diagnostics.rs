@@ -0,0 +1,60 @@
+use crate::{emulator::Address, stack::Stack};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Opt-in heuristics that flag suspicious ROM behaviour. None of these change how a program runs,
+/// they just log a warning and record it in [`crate::emulator::Chip8State::warnings`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostics {
+    /// Warn when `DXYN` draws a sprite made up entirely of zero bytes. This nearly always means I
+    /// points somewhere it shouldn't (a ROM bug or the wrong quirk setting).
+    pub warn_on_blank_sprite: bool,
+    /// Record exactly which pixels collided on every `DXYN` in
+    /// [`crate::emulator::Chip8State::last_collisions`], rather than just setting VF. Handy when
+    /// debugging, or for anything that wants to know where a collision happened.
+    pub report_collisions: bool,
+    /// Warn when the emulator stops with return addresses still on the stack. A well behaved
+    /// program rarely exits mid-subroutine, so this usually means calls and returns don't pair up
+    /// (a ROM bug, or a bug in `2NNN`/`00EE`).
+    pub warn_on_stack_imbalance: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Warning {
+    BlankSprite {
+        index: Address,
+        rows: u8,
+    },
+    /// See [`crate::quirks::ReservedExecution`]
+    ReservedExecution {
+        address: Address,
+    },
+    StackImbalance {
+        stack: Stack,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::BlankSprite { index, rows } => write!(
+                f,
+                "drew a blank {rows}-row sprite from {index}; I may point at uninitialised memory"
+            ),
+            Warning::ReservedExecution { address } => write!(
+                f,
+                "running code at {address}, which is reserved for the font and interpreter; this \
+                 is most likely a runaway jump"
+            ),
+            Warning::StackImbalance { stack } => write!(
+                f,
+                "stopped with {} return addresses left on the stack {stack}; calls and returns may \
+                 not pair up",
+                stack.len()
+            ),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{
+    emulator::Address,
+    opcodes::{DecodeTable, OpCodeData},
+};
+use byteorder::{BigEndian, ByteOrder};
+
+/// Turns `bytes` back into assembly, one instruction per 2-byte word, assuming the first byte
+/// sits at `base`. Returns the address, the raw opcode and the mnemonic of every word. Words we
+/// don't support come out as `DW 0xXXXX` (they're often sprite data rather than code), and an odd
+/// byte left at the end as `DB 0xXX`.
+pub fn disassemble(bytes: &[u8], base: Address) -> Vec<(Address, u16, String)> {
+    let words = bytes.chunks_exact(2);
+    let remainder = words.remainder();
+    let mut lines: Vec<_> = words
+        .enumerate()
+        .map(|(idx, word)| {
+            let opcode = BigEndian::read_u16(word);
+            (offset(base, idx * 2), opcode, mnemonic(opcode))
+        })
+        .collect();
+    if let [byte] = remainder {
+        lines.push((
+            offset(base, bytes.len() - 1),
+            u16::from(*byte),
+            format!("DB 0x{byte:02X}"),
+        ));
+    }
+    lines
+}
+
+/// Assembly for a single opcode, or `DW 0xXXXX` if it's not one we support
+pub fn mnemonic(opcode: u16) -> String {
+    match DecodeTable::shared().lookup(opcode) {
+        Some(instruction) => instruction.mnemonic(&OpCodeData::decode(opcode)),
+        None => format!("DW 0x{opcode:04X}"),
+    }
+}
+
+/// Formats one line of [`disassemble`] output, e.g. `0x200: 6A02  LD VA, 0x02`
+pub fn format_line(address: Address, opcode: u16, mnemonic: &str) -> String {
+    format!("0x{:03X}: {opcode:04X}  {mnemonic}", address.0)
+}
+
+fn offset(base: Address, offset: usize) -> Address {
+    Address(base.0.wrapping_add(offset as u16))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{disassemble, format_line};
+    use crate::emulator::Address;
+    use expect_test::expect;
+    use test_case::test_case;
+
+    #[test]
+    fn test_disassemble() {
+        // LD VA, 0x02; LD I, 0x20A; DRW VA, V1, 5; CALL 0x20A; JP 0x206; sprite data; one byte
+        let program = [
+            0x6A, 0x02, 0xA2, 0x0A, 0xDA, 0x15, 0x22, 0x0A, 0x12, 0x06, 0xF0, 0x90, 0xFF,
+        ];
+        let disassembly: String = disassemble(&program, Address(0x200))
+            .iter()
+            .map(|(address, opcode, mnemonic)| format_line(*address, *opcode, mnemonic) + "\n")
+            .collect();
+        expect![[r#"
+            0x200: 6A02  LD VA, 0x02
+            0x202: A20A  LD I, 0x20A
+            0x204: DA15  DRW VA, V1, 5
+            0x206: 220A  CALL 0x20A
+            0x208: 1206  JP 0x206
+            0x20A: F090  DW 0xF090
+            0x20C: 00FF  DB 0xFF
+        "#]]
+        .assert_eq(&disassembly);
+    }
+
+    #[test_case(0xB123, "JP V0, 0x123")]
+    #[test_case(0x8AB6, "SHR VA, VB")]
+    #[test_case(0xC50F, "RND V5, 0x0F")]
+    #[test_case(0xE29E, "SKP V2")]
+    #[test_case(0xF30A, "LD V3, K")]
+    #[test_case(0xF433, "LD B, V4")]
+    #[test_case(0xF765, "LD V7, [I]")]
+    #[test_case(0x5132, "SAVE V1 - V3")]
+    #[test_case(0xF330, "LD HF, V3")]
+    fn test_mnemonic(opcode: u16, expected: &str) {
+        let bytes = opcode.to_be_bytes();
+        assert_eq!(disassemble(&bytes, Address(0x200))[0].2, expected);
+    }
+}
@@ -1,43 +1,113 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{fmt, ops::Add};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Resolution {
     pub width: usize,
     pub height: usize,
 }
 
+/// The standard CHIP-8 resolution, which SUPER-CHIP calls low resolution
 pub const SCREEN_RES: Resolution = Resolution {
     width: 64,
     height: 32,
 };
 
+/// The SUPER-CHIP high resolution mode
+pub const HIGH_RES: Resolution = Resolution {
+    width: 128,
+    height: 64,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "SerializedDisplay", try_from = "SerializedDisplay")
+)]
 pub struct Display {
-    // Indexed as pixels[y][x]
-    pub pixels: [[bool; SCREEN_RES.width]; SCREEN_RES.height],
+    resolution: Resolution,
+    // Indexed as pixels[y][x]. There are always as many rows and columns as the resolution says,
+    // so only change their size through [`Display::set_resolution`]
+    pub pixels: Vec<Vec<bool>>,
+}
+
+/// Flattened form of [`Display`] used for (de)serialisation, as serde can't handle arrays this
+/// large
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedDisplay {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum Error {
+    #[error("display data is {0}x{1} with {2} pixels, expected 64x32 or 128x64")]
+    InvalidResolution(usize, usize, usize),
 }
 
 impl Default for Display {
     fn default() -> Display {
-        Display {
-            pixels: [[false; SCREEN_RES.width]; SCREEN_RES.height],
-        }
+        Display::new(SCREEN_RES)
     }
 }
 
+/// How sprite bits get combined with the pixels already on the display
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawMode {
+    /// Flip every pixel under a set sprite bit. This is what `DXYN` does, and the only mode that
+    /// reports collisions
+    #[default]
+    Xor,
+    /// Turn on every pixel under a set sprite bit, leaving the rest alone
+    Or,
+    /// Only keep pixels that are lit on both the display and the sprite
+    And,
+    /// Overwrite pixels with the sprite bits, set or not
+    Replace,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Coordinates {
     pub x: u8,
     pub y: u8,
 }
 
 impl Coordinates {
-    #[allow(dead_code)]
+    /// Coordinates wrapped around the low resolution screen
     pub fn new(x: u8, y: u8) -> Coordinates {
         Coordinates {
             x: x % (SCREEN_RES.width as u8),
             y: y % (SCREEN_RES.height as u8),
         }
     }
+
+    /// Builds the starting coordinates of a `DXYN` draw from the values of VX and VY. When
+    /// `wraps` is set (see [`crate::quirks::QuirkConfig::drw_start_wraps`]) a start position off
+    /// the screen at the current `resolution` wraps back round onto it. Otherwise the whole sprite
+    /// is clipped and there's nothing to draw, so this returns `None`. Either way, the sprite
+    /// itself still gets clipped at the edges (see [`Display::apply_sprite`]). Any draw
+    /// coordinate logic should go through here so that behaviour stays in one place.
+    pub fn from_registers(
+        vx: u8,
+        vy: u8,
+        resolution: Resolution,
+        wraps: bool,
+    ) -> Option<Coordinates> {
+        let (x, y) = (usize::from(vx), usize::from(vy));
+        if !wraps && (x >= resolution.width || y >= resolution.height) {
+            return None;
+        }
+        Some(Coordinates {
+            x: (x % resolution.width) as u8,
+            y: (y % resolution.height) as u8,
+        })
+    }
 }
 
 impl Add for Coordinates {
@@ -50,7 +120,70 @@ impl Add for Coordinates {
     }
 }
 
+#[cfg(feature = "serde")]
+impl From<Display> for SerializedDisplay {
+    fn from(display: Display) -> SerializedDisplay {
+        SerializedDisplay {
+            width: display.resolution.width,
+            height: display.resolution.height,
+            pixels: display.pixels.iter().flatten().copied().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedDisplay> for Display {
+    type Error = Error;
+
+    fn try_from(serialized: SerializedDisplay) -> Result<Display, Error> {
+        let resolution = Resolution {
+            width: serialized.width,
+            height: serialized.height,
+        };
+        if ![SCREEN_RES, HIGH_RES].contains(&resolution)
+            || serialized.pixels.len() != resolution.width * resolution.height
+        {
+            return Err(Error::InvalidResolution(
+                serialized.width,
+                serialized.height,
+                serialized.pixels.len(),
+            ));
+        }
+
+        let mut display = Display::new(resolution);
+        for (row, pixels) in display
+            .pixels
+            .iter_mut()
+            .zip(serialized.pixels.chunks(resolution.width))
+        {
+            row.copy_from_slice(pixels);
+        }
+        Ok(display)
+    }
+}
+
 impl Display {
+    /// A blank display at `resolution`
+    pub fn new(resolution: Resolution) -> Display {
+        Display {
+            resolution,
+            pixels: vec![vec![false; resolution.width]; resolution.height],
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Switches to `resolution`, which also clears the display like SUPER-CHIP does
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        if resolution == self.resolution {
+            self.clear();
+        } else {
+            *self = Display::new(resolution);
+        }
+    }
+
     #[cfg(test)]
     pub fn flip_all(&mut self, start: Coordinates, end: Coordinates) {
         for x in start.x..=end.x {
@@ -62,29 +195,194 @@ impl Display {
         }
     }
 
+    /// Iterates over the rows of the display, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.pixels.iter().map(|row| &row[..])
+    }
+
+    /// Iterates over the rows of the display two at a time, for renderers that pack two rows of
+    /// pixels into one line. If there's an odd number of rows the last pair has no bottom row.
+    pub fn pair_rows(&self) -> impl Iterator<Item = (&[bool], Option<&[bool]>)> {
+        pair_rows(&self.pixels)
+    }
+
+    /// Moves everything down by `rows` pixels. Rows pushed off the bottom are lost, and the ones
+    /// uncovered at the top are left blank.
+    pub fn scroll_down(&mut self, rows: usize) {
+        let rows = rows.min(self.pixels.len());
+        self.pixels.rotate_right(rows);
+        for row in &mut self.pixels[..rows] {
+            row.fill(false);
+        }
+    }
+
+    /// Moves everything right by `columns` pixels, leaving the uncovered columns on the left
+    /// blank. Nothing wraps around.
+    pub fn scroll_right(&mut self, columns: usize) {
+        for row in &mut self.pixels {
+            let columns = columns.min(row.len());
+            row.rotate_right(columns);
+            row[..columns].fill(false);
+        }
+    }
+
+    /// Moves everything left by `columns` pixels, leaving the uncovered columns on the right
+    /// blank. Nothing wraps around.
+    pub fn scroll_left(&mut self, columns: usize) {
+        for row in &mut self.pixels {
+            let columns = columns.min(row.len());
+            row.rotate_left(columns);
+            let width = row.len();
+            row[width - columns..].fill(false);
+        }
+    }
+
+    /// Lights up every pixel that's lit in `overlay`, leaving the rest as they are. Useful for
+    /// renderers adding things on top of what the program drew.
+    pub fn overlay(&mut self, overlay: &Display) {
+        for (row, overlay_row) in self.pixels.iter_mut().zip(overlay.pixels.iter()) {
+            for (pixel, overlay_pixel) in row.iter_mut().zip(overlay_row) {
+                *pixel |= *overlay_pixel;
+            }
+        }
+    }
+
+    /// A dotted grid with a line every `spacing` pixels, starting from the top left corner. The
+    /// lines only light every other pixel so they don't drown out what's drawn under them.
+    pub fn grid(resolution: Resolution, spacing: usize) -> Display {
+        let mut grid = Display::new(resolution);
+        for (y, row) in grid.pixels.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = (x % spacing == 0 && y % 2 == 0) || (y % spacing == 0 && x % 2 == 0);
+            }
+        }
+        grid
+    }
+
+    /// Renders the display as an image, drawing every pixel as a `scale`x`scale` block in the `on`
+    /// or `off` colour
+    #[cfg(feature = "image")]
+    pub fn to_image(&self, scale: u32, on: [u8; 3], off: [u8; 3]) -> image::RgbImage {
+        let Resolution { width, height } = self.resolution;
+        image::RgbImage::from_fn(width as u32 * scale, height as u32 * scale, |x, y| {
+            let lit = self.pixels[(y / scale) as usize][(x / scale) as usize];
+            image::Rgb(if lit { on } else { off })
+        })
+    }
+
+    /// Returns the top left and bottom right corners of the smallest box containing every lit
+    /// pixel (both inclusive), or `None` if nothing is lit.
+    pub fn bounding_box(&self) -> Option<(Coordinates, Coordinates)> {
+        let mut corners: Option<(Coordinates, Coordinates)> = None;
+        for (y, row) in self.pixels.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, pixel)| **pixel) {
+                let pixel = Coordinates {
+                    x: x as u8,
+                    y: y as u8,
+                };
+                corners = Some(match corners {
+                    None => (pixel, pixel),
+                    Some((min, max)) => (
+                        Coordinates {
+                            x: min.x.min(pixel.x),
+                            y: min.y.min(pixel.y),
+                        },
+                        Coordinates {
+                            x: max.x.max(pixel.x),
+                            y: max.y.max(pixel.y),
+                        },
+                    ),
+                });
+            }
+        }
+        corners
+    }
+
     pub fn clear(&mut self) {
-        self.pixels[..].copy_from_slice(&[[false; SCREEN_RES.width]; SCREEN_RES.height]);
+        for row in &mut self.pixels {
+            row.fill(false);
+        }
+    }
+
+    /// Draws an 8-pixel wide sprite with its top left corner at `coordinates`, clipping anything
+    /// past the right and bottom edges. Returns whether any lit pixel got turned off, which is
+    /// only ever the case in [`DrawMode::Xor`].
+    pub fn apply_sprite(
+        &mut self,
+        sprite: &[u8],
+        coordinates: Coordinates,
+        mode: DrawMode,
+    ) -> bool {
+        !self
+            .apply_sprite_collisions(sprite, coordinates, mode)
+            .is_empty()
+    }
+
+    /// Same as [`Display::apply_sprite`], but returns where every collision happened rather than
+    /// just whether there was one.
+    pub fn apply_sprite_collisions(
+        &mut self,
+        sprite: &[u8],
+        coordinates: Coordinates,
+        mode: DrawMode,
+    ) -> Vec<Coordinates> {
+        self.apply_rows(sprite, 1, coordinates, mode)
     }
 
-    pub fn apply_sprite(&mut self, sprite: &[u8], coordinates: Coordinates) {
-        for (y_offset, byte) in sprite.iter().enumerate() {
+    /// Same as [`Display::apply_sprite_collisions`], but for the 16x16 sprites SUPER-CHIP draws
+    /// in high resolution, which take two bytes per row.
+    pub fn apply_wide_sprite_collisions(
+        &mut self,
+        sprite: &[u8],
+        coordinates: Coordinates,
+        mode: DrawMode,
+    ) -> Vec<Coordinates> {
+        self.apply_rows(sprite, 2, coordinates, mode)
+    }
+
+    fn apply_rows(
+        &mut self,
+        sprite: &[u8],
+        bytes_per_row: usize,
+        coordinates: Coordinates,
+        mode: DrawMode,
+    ) -> Vec<Coordinates> {
+        let mut collisions = Vec::new();
+        let len_bits = (bytes_per_row * 8)
+            .try_into()
+            .expect("sprite row width does not fit in u8");
+        for (y_offset, row) in sprite.chunks(bytes_per_row).enumerate() {
             // Truncate y coordinates as soon as possible
-            if y_offset + (coordinates.y as usize) >= 32 {
+            if y_offset + (coordinates.y as usize) >= self.resolution.height {
                 break;
             }
 
             let y_offset = y_offset
                 .try_into()
                 .expect("y offset did not fit in a usize");
-            self.apply_row(&[*byte], 8, coordinates + Coordinates::new(0, y_offset));
+            self.apply_row(
+                row,
+                len_bits,
+                coordinates + Coordinates { x: 0, y: y_offset },
+                mode,
+                &mut collisions,
+            );
         }
+        collisions
     }
 
-    fn apply_row(&mut self, row: &[u8], len_bits: u8, coordinates: Coordinates) {
+    fn apply_row(
+        &mut self,
+        row: &[u8],
+        len_bits: u8,
+        coordinates: Coordinates,
+        mode: DrawMode,
+        collisions: &mut Vec<Coordinates>,
+    ) {
         let full_row: &mut [bool] = &mut self.pixels[coordinates.y as usize];
         let start = coordinates.x;
         let end = (coordinates.x + len_bits).min(
-            SCREEN_RES
+            self.resolution
                 .width
                 .try_into()
                 .expect("screen resolution does not fit in u8"),
@@ -100,9 +398,21 @@ impl Display {
             let bit_in_byte = 7 - (x % 8);
             let val = (row[byte] & (1 << bit_in_byte)) != 0;
             let idx: usize = (start + x).into();
-            if val {
-                full_row[idx] = !full_row[idx];
-            }
+            let pixel = full_row[idx];
+            full_row[idx] = match mode {
+                DrawMode::Xor => {
+                    if val && pixel {
+                        collisions.push(Coordinates {
+                            x: start + x,
+                            y: coordinates.y,
+                        });
+                    }
+                    pixel != val
+                }
+                DrawMode::Or => pixel || val,
+                DrawMode::And => pixel && val,
+                DrawMode::Replace => val,
+            };
         }
     }
 
@@ -125,6 +435,11 @@ impl Display {
     }
 }
 
+fn pair_rows<R: AsRef<[bool]>>(rows: &[R]) -> impl Iterator<Item = (&[bool], Option<&[bool]>)> {
+    rows.chunks(2)
+        .map(|pair| (pair[0].as_ref(), pair.get(1).map(|row| row.as_ref())))
+}
+
 impl fmt::Display for Display {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Top row cover
@@ -135,10 +450,10 @@ impl fmt::Display for Display {
         writeln!(f, ".")?;
 
         // Pixel rows
-        for y in 0..self.pixels.len() {
+        for row in self.rows() {
             write!(f, "|")?;
-            for x in 0..self.pixels[y].len() {
-                if self.pixels[y][x] {
+            for pixel in row {
+                if *pixel {
                     write!(f, "█")?;
                 } else {
                     write!(f, " ")?;
@@ -157,3 +472,204 @@ impl fmt::Display for Display {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{pair_rows, Coordinates, Display, DrawMode, Resolution, HIGH_RES, SCREEN_RES};
+    use test_case::test_case;
+
+    #[test_case(12, 7, SCREEN_RES, 12, 7; "in_bounds")]
+    #[test_case(63, 31, SCREEN_RES, 63, 31; "bottom_right_corner")]
+    #[test_case(64, 32, SCREEN_RES, 0, 0; "wraps_to_origin")]
+    #[test_case(120, 136, SCREEN_RES, 56, 8; "wraps_multiple_times")]
+    #[test_case(255, 255, SCREEN_RES, 63, 31; "max_register_values")]
+    #[test_case(64, 32, HIGH_RES, 64, 32; "high_res_in_bounds")]
+    #[test_case(255, 255, HIGH_RES, 127, 63; "high_res_max_register_values")]
+    fn test_coordinates_from_registers(vx: u8, vy: u8, resolution: Resolution, x: u8, y: u8) {
+        assert_eq!(
+            Coordinates::from_registers(vx, vy, resolution, true),
+            Some(Coordinates { x, y })
+        );
+    }
+
+    #[test_case(63, 31, SCREEN_RES, Some((63, 31)); "bottom_right_corner")]
+    #[test_case(64, 0, SCREEN_RES, None; "past_right_edge")]
+    #[test_case(0, 32, SCREEN_RES, None; "past_bottom_edge")]
+    #[test_case(255, 255, SCREEN_RES, None; "max_register_values")]
+    #[test_case(64, 32, HIGH_RES, Some((64, 32)); "high_res_in_bounds")]
+    #[test_case(128, 63, HIGH_RES, None; "high_res_past_right_edge")]
+    fn test_coordinates_from_registers_clipped(
+        vx: u8,
+        vy: u8,
+        resolution: Resolution,
+        expected: Option<(u8, u8)>,
+    ) {
+        assert_eq!(
+            Coordinates::from_registers(vx, vy, resolution, false),
+            expected.map(|(x, y)| Coordinates { x, y })
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut display = Display::default();
+        display.pixels[3][5] = true;
+        let rows: Vec<&[bool]> = display.rows().collect();
+        assert_eq!(rows.len(), SCREEN_RES.height);
+        assert!(rows.iter().all(|row| row.len() == SCREEN_RES.width));
+        assert!(rows[3][5]);
+    }
+
+    #[test]
+    fn test_pair_rows_even() {
+        let display = Display::default();
+        assert_eq!(display.pair_rows().count(), SCREEN_RES.height / 2);
+        assert!(display.pair_rows().all(|(_, bottom)| bottom.is_some()));
+    }
+
+    #[test]
+    fn test_pair_rows_odd() {
+        let rows = [[true, false], [false, true], [true, true]];
+        let pairs: Vec<_> = pair_rows(&rows).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (&[true, false][..], Some(&[false, true][..])),
+                (&[true, true][..], None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scroll_past_bottom() {
+        let mut display = Display::grid(SCREEN_RES, 8);
+        display.scroll_down(SCREEN_RES.height + 1);
+        assert_eq!(display, Display::default());
+    }
+
+    // Draws 0b1100 over a display with 0b1010 already drawn, so each bit combination shows up
+    #[test_case(DrawMode::Xor,     [false, true,  true,  false], true;  "xor")]
+    #[test_case(DrawMode::Or,      [true,  true,  true,  false], false; "or")]
+    #[test_case(DrawMode::And,     [true,  false, false, false], false; "and")]
+    #[test_case(DrawMode::Replace, [true,  true,  false, false], false; "replace")]
+    fn test_apply_sprite_draw_modes(mode: DrawMode, expected: [bool; 4], collided: bool) {
+        let mut display = Display::default();
+        let origin = Coordinates::new(0, 0);
+        assert!(!display.apply_sprite(&[0b1010_0000], origin, DrawMode::Xor));
+        assert_eq!(display.apply_sprite(&[0b1100_0000], origin, mode), collided);
+        assert_eq!(display.pixels[0][..4], expected);
+    }
+
+    #[test]
+    fn test_grid_overlay() {
+        let mut display = Display::default();
+        display.pixels[3][5] = true;
+        display.overlay(&Display::grid(SCREEN_RES, 8));
+
+        // The pixel that was already lit stays lit
+        assert!(display.pixels[3][5]);
+        // Vertical lines at x = 0, 8, 16, ..., dotted on even rows
+        assert!(display.pixels[2][8] && display.pixels[4][56]);
+        assert!(!display.pixels[3][8]);
+        // Horizontal lines at y = 0, 8, 16 and 24, dotted on even columns
+        assert!(display.pixels[8][2] && display.pixels[24][62]);
+        assert!(!display.pixels[8][3]);
+        // Nothing in between the lines
+        assert!(!display.pixels[4][4] && !display.pixels[31][63]);
+        let lit = display
+            .pixels
+            .iter()
+            .flatten()
+            .filter(|pixel| **pixel)
+            .count();
+        // 8 vertical lines of 16 dots and 4 horizontal ones of 32, minus the 32 crossings
+        assert_eq!(lit, 8 * 16 + 4 * 32 - 32 + 1);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mut display = Display::default();
+        assert_eq!(display.bounding_box(), None);
+
+        // A 0 from the built-in font: 4 pixels wide and 5 tall
+        display.apply_sprite(
+            &[0xF0, 0x90, 0x90, 0x90, 0xF0],
+            Coordinates::new(20, 9),
+            DrawMode::Xor,
+        );
+        assert_eq!(
+            display.bounding_box(),
+            Some((Coordinates { x: 20, y: 9 }, Coordinates { x: 23, y: 13 }))
+        );
+
+        display.apply_sprite(&[0b0100_0000], Coordinates::new(2, 30), DrawMode::Xor);
+        assert_eq!(
+            display.bounding_box(),
+            Some((Coordinates { x: 3, y: 9 }, Coordinates { x: 23, y: 30 }))
+        );
+    }
+
+    #[test]
+    fn test_apply_sprite_collisions() {
+        let mut display = Display::default();
+        display.apply_sprite(&[0b0110_0000], Coordinates::new(10, 4), DrawMode::Xor);
+        // Overlaps the two lit pixels at x=11 and x=12, and draws one more at x=13
+        let collisions =
+            display.apply_sprite_collisions(&[0b1110_0000], Coordinates::new(11, 4), DrawMode::Xor);
+        assert_eq!(
+            collisions,
+            vec![Coordinates { x: 11, y: 4 }, Coordinates { x: 12, y: 4 }]
+        );
+    }
+
+    #[test]
+    fn test_high_res() {
+        let mut display = Display::default();
+        display.pixels[0][0] = true;
+        display.set_resolution(HIGH_RES);
+        assert_eq!(display, Display::new(HIGH_RES));
+        assert_eq!(display.pixels.len(), 64);
+        assert!(display.pixels.iter().all(|row| row.len() == 128));
+
+        // Sprites are only clipped at the high resolution edges
+        display.apply_sprite(&[0xFF, 0xFF], Coordinates { x: 124, y: 63 }, DrawMode::Xor);
+        assert_eq!(
+            display.bounding_box(),
+            Some((Coordinates { x: 124, y: 63 }, Coordinates { x: 127, y: 63 }))
+        );
+        assert_eq!(display.to_string().lines().count(), 66);
+        assert!(display
+            .to_string()
+            .lines()
+            .all(|line| line.chars().count() == 130));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialise_high_res() {
+        let mut display = Display::new(HIGH_RES);
+        display.pixels[63][127] = true;
+        let serialized = bincode::serialize(&display).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Display>(&serialized).unwrap(),
+            display
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_to_image() {
+        let (on, off) = ([0xFF, 0xB0, 0x00], [0x10, 0x10, 0x10]);
+        let mut display = Display::default();
+        let image = display.to_image(3, on, off);
+        assert_eq!(image.dimensions(), (64 * 3, 32 * 3));
+        assert!(image.pixels().all(|pixel| pixel.0 == off));
+
+        display.pixels[1][2] = true;
+        let image = display.to_image(3, on, off);
+        assert_eq!(image.get_pixel(6, 3).0, on);
+        assert_eq!(image.get_pixel(8, 5).0, on);
+        assert_eq!(image.get_pixel(9, 5).0, off);
+        assert_eq!(image.pixels().filter(|pixel| pixel.0 == on).count(), 9);
+    }
+}
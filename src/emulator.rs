@@ -1,34 +1,162 @@
 use crate::{
-    display::Display,
+    coverage::Coverage,
+    diagnostics::{Diagnostics, Warning},
+    display::{Coordinates, Display},
     font::Chip8Font,
-    opcodes::{self, OpCodeData, OpCodeReader},
-    program::Program,
+    hash::StableHasher,
+    heatmap::MemoryHeatmap,
+    opcodes::{DecodeTable, OpCodeData},
+    program::{Program, PROGRAM_START},
+    quirks::{QuirkConfig, ReservedExecution},
+    renderer::{Command, Renderer},
+    rng::Chip8Rng,
+    save_state::{self, SLOT_COUNT},
+    stack::{Stack, STACK_DEPTH},
+    timing::{adjust_speed, FramePacer, RateRamp, FRAME_PERIOD, YIELD_PERIOD},
+    trace::TraceEntry,
 };
 use byteorder::{BigEndian, ByteOrder};
-use log::debug;
-use std::{collections::VecDeque, fmt, time::Duration};
+use log::{debug, error, info, warn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_big_array::BigArray;
+use spin_sleep::LoopHelper;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    io::Write,
+    ops::Range,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+/// How many instructions get traced at most unless set through
+/// [`EmulatedChip8::with_trace_limit`]. Each entry is small, so this is only a few MB.
+pub const DEFAULT_TRACE_LIMIT: usize = 100_000;
 
 pub struct EmulatedChip8 {
     state: Chip8State,
-    supported_instructions: Vec<Box<dyn OpCodeReader>>,
+    decode_table: &'static DecodeTable,
+    skip_unknown_opcodes: bool,
+    rom_hash: Option<u64>,
+    save_dir: PathBuf,
+    coverage: Option<Coverage>,
+    speed_ramp: Duration,
+    one_draw_per_frame: bool,
+    /// Tracks the 60Hz frame boundaries in emulated time
+    frame_clock: FramePacer,
+    /// Set once a `DXYN` has run this frame, while [`EmulatedChip8::with_one_draw_per_frame`] is on
+    waiting_for_frame: bool,
+    frame_log: Option<Box<dyn Write + Send>>,
+    frame_handler: Option<FrameHandler>,
+    recovery: Option<RecoveryHandler>,
+    /// While set, the timers hold their values, see [`EmulatedChip8::set_timers_paused`]
+    timers_paused: bool,
+    /// The most recent displays, oldest first, see [`EmulatedChip8::with_display_history`]
+    display_history: VecDeque<Display>,
+    display_history_len: usize,
+    /// Instructions run so far, oldest first, see [`EmulatedChip8::enable_trace`]
+    trace: VecDeque<TraceEntry>,
+    trace_enabled: bool,
+    trace_limit: usize,
+    /// Addresses [`EmulatedChip8::step_until_breakpoint`] stops at
+    breakpoints: HashSet<Address>,
+}
+
+/// What happened during [`EmulatedChip8::step_until_breakpoint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StepOutcome {
+    /// An instruction ran as usual
+    Stepped,
+    /// PC was at a breakpoint, so nothing ran
+    HitBreakpoint(Address),
 }
 
+/// What to do after running into an opcode we don't support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Recovery {
+    /// Carry on with the next instruction, as if the opcode was a no-op
+    Skip,
+    /// Stop with [`Error::UnsupportedOpcode`]
+    Abort,
+}
+
+/// Decides how to recover from an unsupported opcode, given the address it's at and the opcode
+pub type RecoveryHandler = Box<dyn FnMut(Address, u16) -> Recovery + Send>;
+
+/// Gets called at every 60Hz frame boundary (the "vblank"), with the state as the frame ends
+pub type FrameHandler = Box<dyn FnMut(&Chip8State) + Send>;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Chip8State {
+    #[cfg_attr(feature = "serde", serde(with = "BigArray"))]
     pub memory: [u8; 4096],
     pub display: Display,
     pub pc: Address,
-    pub stack: VecDeque<Address>,
+    pub stack: Stack,
     pub index_register: Address,
     pub delay_timer: Register,
-    pub since_last_delay_update: Duration,
     pub sound_timer: Register,
-    pub since_last_sound_update: Duration,
+    /// Time since the timers last counted down, towards the next 60Hz tick
+    pub since_last_timer_tick: Duration,
     pub gp_registers: [Register; 16],
     pub key_state: KeyInput,
+    pub rng: Chip8Rng,
+    /// How the emulator was set up rather than part of the machine, so this and `quirks` are left
+    /// out of save states
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub diagnostics: Diagnostics,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub quirks: QuirkConfig,
+    /// Everything flagged by the enabled [`Diagnostics`] so far. Each warning is only recorded
+    /// once.
+    pub warnings: Vec<Warning>,
+    /// Pixels that collided during the last `DXYN`. Only filled in when
+    /// [`Diagnostics::report_collisions`] is on.
+    pub last_collisions: Vec<Coordinates>,
+    /// Memory access counts, when enabled through [`EmulatedChip8::with_heatmap`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub heatmap: Option<MemoryHeatmap>,
+}
+
+/// The registers, timers and stack depth of a [`Chip8State`], small enough to hand to the
+/// renderer every frame for debugging displays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisterSnapshot {
+    pub registers: [u8; 16],
+    pub index_register: Address,
+    pub pc: Address,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub stack_depth: usize,
+}
+
+impl RegisterSnapshot {
+    pub fn capture(state: &Chip8State) -> RegisterSnapshot {
+        RegisterSnapshot {
+            registers: state.gp_registers.map(|register| register.0),
+            index_register: state.index_register,
+            pc: state.pc,
+            delay_timer: state.delay_timer.0,
+            sound_timer: state.sound_timer.0,
+            stack_depth: state.stack.len(),
+        }
+    }
+}
+
+impl Default for RegisterSnapshot {
+    /// The registers of a machine that hasn't started running yet
+    fn default() -> RegisterSnapshot {
+        RegisterSnapshot::capture(&Chip8State::new())
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct KeyInput {
     pub key_state: [bool; 0x10],
 }
@@ -37,10 +165,40 @@ pub struct KeyInput {
 pub enum Error {
     #[error("the opcode {0:#06x} is unsupported")]
     UnsupportedOpcode(u16),
+    #[error("{0} is outside of memory")]
+    AddressOutOfRange(Address),
+    #[error("PC ({0:#06x}) doesn't leave room for a whole instruction before the end of memory")]
+    PcOutOfBounds(u16),
+    #[error(transparent)]
+    Fault(#[from] Fault),
+}
+
+/// Problems an opcode can run into while executing, that stop the program
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum Fault {
+    #[error("key value {0:#04x} is out of range, keys go from 0x0 to 0xF")]
+    KeyOutOfRange(u8),
+    #[error("accessing memory at I ({index}) + {offset} goes past the end of memory")]
+    IndexOutOfRange { index: Address, offset: u16 },
+    #[error("tried to run code at {0}, which is reserved for the font and interpreter")]
+    ReservedExecution(Address),
+    #[error("subroutine call at {0} nests too deep, the stack only has room for {STACK_DEPTH}")]
+    StackOverflow(Address),
+    #[error("return at {0} with no subroutine to return from")]
+    StackUnderflow(Address),
 }
 
 pub type Result<T = (), E = Error> = std::result::Result<T, E>;
 
+impl KeyInput {
+    /// Returns the lowest-numbered key currently pressed. Whenever several keys are held at once,
+    /// we scan upwards from key 0x0, so opcodes waiting on "any key" behave deterministically
+    /// (which matters for replays).
+    pub fn first_pressed(&self) -> Option<u8> {
+        (0..0x10u8).find(|key| self.key_state[usize::from(*key)])
+    }
+}
+
 impl EmulatedChip8 {
     /// Creates a new, empty, uninitialised emulated chip 8
     /// Usually you'd call this, followed by [`EmulatedChip8::write_font`],
@@ -48,45 +206,127 @@ impl EmulatedChip8 {
     pub fn new() -> EmulatedChip8 {
         EmulatedChip8 {
             state: Chip8State::new(),
-            supported_instructions: vec![
-                Box::new(opcodes::ClearScreen),
-                Box::new(opcodes::Jump),
-                Box::new(opcodes::SetRegisterConst),
-                Box::new(opcodes::AddRegisterConst),
-                Box::new(opcodes::SetIndexRegister),
-                Box::new(opcodes::DisplayDraw),
-                Box::new(opcodes::SubroutineCall),
-                Box::new(opcodes::SubroutineReturn),
-                Box::new(opcodes::SkipConstEqual),
-                Box::new(opcodes::SkipConstNotEqual),
-                Box::new(opcodes::SkipRegistersEqual),
-                Box::new(opcodes::SkipRegistersNotEqual),
-                Box::new(opcodes::SetRegisterRegister),
-                Box::new(opcodes::BinaryOr),
-                Box::new(opcodes::BinaryAnd),
-                Box::new(opcodes::BinaryXor),
-                Box::new(opcodes::AddRegisters),
-                Box::new(opcodes::SubtractRegisters),
-                Box::new(opcodes::SubtractRegistersReverse),
-                Box::new(opcodes::ShiftRegisterRight),
-                Box::new(opcodes::ShiftRegisterLeft),
-                Box::new(opcodes::JumpOffset),
-                Box::new(opcodes::Random),
-                Box::new(opcodes::SkipIfKey),
-                Box::new(opcodes::SkipIfNotKey),
-                Box::new(opcodes::ReadDelayTimer),
-                Box::new(opcodes::SetDelayTimer),
-                Box::new(opcodes::SetSoundTimer),
-                Box::new(opcodes::AddIndexRegister),
-                Box::new(opcodes::GetKey),
-                Box::new(opcodes::ReadFontCharacter),
-                Box::new(opcodes::DecimalDecoding),
-                Box::new(opcodes::StoreMemory),
-                Box::new(opcodes::LoadMemory),
-            ],
+            decode_table: DecodeTable::shared(),
+            skip_unknown_opcodes: false,
+            rom_hash: None,
+            save_dir: PathBuf::from("."),
+            coverage: None,
+            speed_ramp: Duration::ZERO,
+            one_draw_per_frame: false,
+            frame_clock: FramePacer::default(),
+            waiting_for_frame: false,
+            frame_log: None,
+            frame_handler: None,
+            recovery: None,
+            timers_paused: false,
+            display_history: VecDeque::new(),
+            display_history_len: 0,
+            trace: VecDeque::new(),
+            trace_enabled: false,
+            trace_limit: DEFAULT_TRACE_LIMIT,
+            breakpoints: HashSet::new(),
         }
     }
 
+    /// When set, opcodes we don't support are logged and skipped over instead of returning
+    /// [`Error::UnsupportedOpcode`]. Useful for ROMs with stray data bytes in them.
+    pub fn with_skip_unknown_opcodes(mut self, skip_unknown_opcodes: bool) -> EmulatedChip8 {
+        self.skip_unknown_opcodes = skip_unknown_opcodes;
+        self
+    }
+
+    /// Asks `handler` what to do whenever we run into an opcode we don't support, instead of
+    /// always stopping. Handy while implementing new opcodes, to decide case by case whether a
+    /// missing one matters. Has no effect with [`EmulatedChip8::with_skip_unknown_opcodes`] on.
+    pub fn with_recovery(mut self, handler: RecoveryHandler) -> EmulatedChip8 {
+        self.recovery = Some(handler);
+        self
+    }
+
+    /// When set, the CPU stalls after every `DXYN` until the next 60Hz frame starts, so at most
+    /// one draw happens per frame. Some games flicker when they get to draw more often than that.
+    /// Timers keep running while stalled.
+    pub fn with_one_draw_per_frame(mut self, one_draw_per_frame: bool) -> EmulatedChip8 {
+        self.one_draw_per_frame = one_draw_per_frame;
+        self
+    }
+
+    /// Writes a one line [`Chip8State::summary`] to `frame_log` at every 60Hz frame boundary.
+    /// This gives a rough timeline of a run without the cost of tracing every instruction.
+    pub fn with_frame_log(mut self, frame_log: Box<dyn Write + Send>) -> EmulatedChip8 {
+        self.frame_log = Some(frame_log);
+        self
+    }
+
+    /// Calls `handler` at every 60Hz frame boundary in emulated time, however many instructions
+    /// run per frame. Frontends can use it to present video and audio in sync with the emulator,
+    /// the same way the timers and [`EmulatedChip8::with_one_draw_per_frame`] see frames.
+    pub fn with_frame_handler(mut self, handler: FrameHandler) -> EmulatedChip8 {
+        self.frame_handler = Some(handler);
+        self
+    }
+
+    /// Keeps a copy of the display after each of the last `len` instructions that changed it
+    /// (`DXYN` and `00E0`), to scrub back through when tracking down a visual glitch. Unlike save
+    /// states this only keeps the display, so it's cheap enough to leave on.
+    pub fn with_display_history(mut self, len: usize) -> EmulatedChip8 {
+        self.display_history = VecDeque::with_capacity(len);
+        self.display_history_len = len;
+        self
+    }
+
+    /// How many instructions [`EmulatedChip8::enable_trace`] keeps at most. Past that, the oldest
+    /// ones get dropped. Defaults to [`DEFAULT_TRACE_LIMIT`]
+    pub fn with_trace_limit(mut self, trace_limit: usize) -> EmulatedChip8 {
+        self.trace_limit = trace_limit;
+        self
+    }
+
+    /// Sets which diagnostic checks get run while executing the program
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> EmulatedChip8 {
+        self.state.diagnostics = diagnostics;
+        self
+    }
+
+    /// Sets which interpreter-specific behaviours the opcodes follow
+    pub fn with_quirks(mut self, quirks: QuirkConfig) -> EmulatedChip8 {
+        self.state.quirks = quirks;
+        self
+    }
+
+    /// When set, every executed address and opcode gets recorded, see
+    /// [`EmulatedChip8::coverage`]
+    pub fn with_coverage(mut self, coverage: bool) -> EmulatedChip8 {
+        self.coverage = coverage.then(Coverage::default);
+        self
+    }
+
+    /// How long [`EmulatedChip8::run`] takes to ease into a new speed. Zero (the default) switches
+    /// straight away
+    pub fn with_speed_ramp(mut self, speed_ramp: Duration) -> EmulatedChip8 {
+        self.speed_ramp = speed_ramp;
+        self
+    }
+
+    /// When set, every memory read and write made by the program gets counted, see
+    /// [`EmulatedChip8::heatmap`]
+    pub fn with_heatmap(mut self, heatmap: bool) -> EmulatedChip8 {
+        self.state.heatmap = heatmap.then(MemoryHeatmap::default);
+        self
+    }
+
+    /// Seeds the random number generator used by `CXNN`, so runs are reproducible
+    pub fn with_seed(mut self, seed: u64) -> EmulatedChip8 {
+        self.state.rng = Chip8Rng::from_seed(seed);
+        self
+    }
+
+    /// Sets the directory save slot files get written to and read from
+    pub fn with_save_dir(mut self, save_dir: PathBuf) -> EmulatedChip8 {
+        self.save_dir = save_dir;
+        self
+    }
+
     /// Use this to write a font to the appropriate location in memory.
     /// # Arguments
     /// * `font` - The font data to load onto memory
@@ -99,41 +339,378 @@ impl EmulatedChip8 {
     /// * `program` - The program data to load onto memory
     pub fn load_program(&mut self, program: &Program) {
         program.load(&mut self.state);
+        self.rom_hash = Some(program.hash());
+    }
+
+    /// Restarts the loaded program, like pressing reset on the machine. Registers, the stack,
+    /// the display, timers and warnings are all cleared and PC goes back to the program start,
+    /// but memory is left alone, so the font and program don't need loading again. Quirks,
+    /// diagnostics and the random number generator carry on as configured.
+    pub fn reset(&mut self) {
+        let fresh = Chip8State::new();
+        let state = std::mem::replace(&mut self.state, fresh);
+        self.state.memory = state.memory;
+        self.state.rng = state.rng;
+        self.state.diagnostics = state.diagnostics;
+        self.state.quirks = state.quirks;
+        self.state.heatmap = state.heatmap;
+        self.state.pc = PROGRAM_START;
+        self.frame_clock = FramePacer::default();
+        self.waiting_for_frame = false;
+        self.display_history.clear();
+    }
+
+    /// Like [`EmulatedChip8::reset`], but also zeroes the whole of memory. The font and a
+    /// program have to be loaded again before running anything.
+    pub fn reset_hard(&mut self) {
+        self.reset();
+        self.state.memory = [0; 4096];
+        self.rom_hash = None;
+    }
+
+    /// Moves the program counter, e.g. to start execution somewhere other than the program's
+    /// entry point. Fails if there's no room for an instruction at `pc`. Odd addresses are
+    /// allowed, as some ROMs do place code there, so it's up to the caller to warn about them.
+    pub fn set_pc(&mut self, pc: Address) -> Result {
+        if usize::from(pc.0) + 2 > self.state.memory.len() {
+            return Err(Error::AddressOutOfRange(pc));
+        }
+        self.state.pc = pc;
+        Ok(())
+    }
+
+    /// Serialises the full machine state so it can be restored later through
+    /// [`EmulatedChip8::load_state`].
+    #[cfg(feature = "serde")]
+    pub fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(&self.state).expect("chip 8 state should always be serialisable")
+    }
+
+    /// Restores a machine state previously produced by [`EmulatedChip8::save_state`]. The quirks,
+    /// diagnostics and heatmap aren't part of the machine, so they're left as this emulator was
+    /// set up.
+    #[cfg(feature = "serde")]
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), save_state::Error> {
+        let loaded: Chip8State =
+            bincode::deserialize(bytes).map_err(save_state::Error::InvalidData)?;
+        self.state = Chip8State {
+            quirks: self.state.quirks,
+            diagnostics: self.state.diagnostics,
+            heatmap: self.state.heatmap.take(),
+            ..loaded
+        };
+        Ok(())
+    }
+
+    /// Saves the current state to one of the quick save slots of the loaded ROM.
+    /// # Arguments
+    /// * `slot` - Slot to save to, from 1 to [`SLOT_COUNT`]
+    #[cfg(feature = "serde")]
+    pub fn save_slot(&self, slot: u8) -> Result<(), save_state::Error> {
+        let path = self.slot_path(slot)?;
+        std::fs::write(&path, self.save_state()).map_err(|e| save_state::Error::Io(path, e))
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn save_slot(&self, slot: u8) -> Result<(), save_state::Error> {
+        self.slot_path(slot)?;
+        Err(save_state::Error::Unsupported)
+    }
+
+    /// Restores the state saved in one of the quick save slots of the loaded ROM.
+    /// # Arguments
+    /// * `slot` - Slot to load from, from 1 to [`SLOT_COUNT`]
+    #[cfg(feature = "serde")]
+    pub fn load_slot(&mut self, slot: u8) -> Result<(), save_state::Error> {
+        let path = self.slot_path(slot)?;
+        let bytes = std::fs::read(&path).map_err(|e| save_state::Error::Io(path, e))?;
+        self.load_state(&bytes)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn load_slot(&mut self, slot: u8) -> Result<(), save_state::Error> {
+        self.slot_path(slot)?;
+        Err(save_state::Error::Unsupported)
+    }
+
+    fn slot_path(&self, slot: u8) -> Result<PathBuf, save_state::Error> {
+        if !(1..=SLOT_COUNT).contains(&slot) {
+            return Err(save_state::Error::InvalidSlot(slot));
+        }
+        let rom_hash = self.rom_hash.ok_or(save_state::Error::NoProgramLoaded)?;
+        Ok(save_state::slot_path(&self.save_dir, rom_hash, slot))
+    }
+
+    /// Runs the loaded program until the renderer terminates, stepping the CPU at `speed`
+    /// instructions per second and sending the display to the renderer at 60Hz. The renderer gets
+    /// dropped before this returns, so it's had a chance to clean up.
+    pub fn run<R: Renderer>(&mut self, mut renderer: R, speed: f64) -> anyhow::Result<()> {
+        let mut pacer = FramePacer::default();
+        let mut yield_pacer = FramePacer::new(YIELD_PERIOD);
+        let mut lh = LoopHelper::builder().build_with_target_rate(speed);
+        let mut expected_period = Duration::from_secs_f64(1. / speed);
+        let mut speed_ramp = RateRamp::new(speed, self.speed_ramp);
+        renderer.update_speed(speed)?;
+
+        loop {
+            let elapsed = lh.loop_start();
+
+            if !speed_ramp.settled() {
+                let speed = speed_ramp.advance(elapsed);
+                lh.set_target_rate(speed);
+                expected_period = Duration::from_secs_f64(1. / speed);
+                renderer.update_speed(speed)?;
+            }
+
+            // Check if screen is still alive
+            if renderer.terminated() {
+                info!("terminating program");
+                self.check_exit_state();
+                debug!("final state:\n{}", self.state);
+                return Ok(());
+            }
+
+            while let Some(command) = renderer.next_command() {
+                let result = match command {
+                    Command::SaveSlot(slot) => self.save_slot(slot),
+                    Command::LoadSlot(slot) => self.load_slot(slot),
+                    Command::SpeedUp | Command::SlowDown => {
+                        let faster = command == Command::SpeedUp;
+                        speed_ramp.set_target(adjust_speed(speed_ramp.target(), faster));
+                        info!("speed set to {:.0}Hz", speed_ramp.target());
+                        Ok(())
+                    }
+                };
+                match result {
+                    Ok(()) => info!("{command:?} succeeded"),
+                    Err(e) => error!("{command:?} failed: {e}"),
+                }
+            }
+
+            // Fetch key state
+            let key_input = renderer.current_key_state();
+
+            // The timers run on real time rather than instruction time, so they stay at 60Hz even
+            // when the instruction rate is far off (or the loop can't keep up with it)
+            if !renderer.paused() {
+                self.tick_timers(elapsed);
+                self.step_cpu(key_input, expected_period)?;
+            }
+            if pacer.present(elapsed) {
+                renderer.update_screen(&self.state.display)?;
+                renderer.update_sound_timer(self.state.sound_timer)?;
+                renderer.update_registers(&RegisterSnapshot::capture(&self.state))?;
+                renderer.set_buzzer(self.state.sound_timer.0 > 0)?;
+            }
+            if yield_pacer.present(elapsed) {
+                thread::yield_now();
+            }
+            lh.loop_sleep();
+        }
     }
 
     /// Runs a single step on the CPU. In this case, this practically will execute a full
-    /// fetch-decode-execute loop on the emulated CPU. We also expect you to provide keyboard input
+    /// fetch-decode-execute loop on the emulated CPU. We also expect you to provide keyboard input.
+    /// The timers count down by `time_delta` too, see [`EmulatedChip8::tick_timers`]
     pub fn step(&mut self, key_input: KeyInput, time_delta: Duration) -> Result {
+        self.tick_timers(time_delta);
+        self.step_cpu(key_input, time_delta)
+    }
+
+    /// [`EmulatedChip8::step`] without touching the timers, for when they're driven separately
+    fn step_cpu(&mut self, key_input: KeyInput, time_delta: Duration) -> Result {
         self.state.key_state = key_input;
-        self.update_timers(time_delta);
-        let opcode_bytes = self.fetch();
+        self.advance_frame(time_delta);
+        if self.waiting_for_frame {
+            return Ok(());
+        }
+        if self.trace_enabled && self.trace_limit > 0 {
+            if self.trace.len() == self.trace_limit {
+                self.trace.pop_front();
+            }
+            self.trace.push_back(TraceEntry::capture(&self.state));
+        }
+        let opcode_bytes = self.fetch()?;
         let opcode_data = self.decode(opcode_bytes);
         self.execute(opcode_data)
     }
 
+    /// Like [`EmulatedChip8::step`], but stops short of running the instruction if PC is at a
+    /// breakpoint. Time doesn't pass either, so the timers hold while stopped. Use
+    /// [`EmulatedChip8::step`] to carry on past the breakpoint.
+    pub fn step_until_breakpoint(
+        &mut self,
+        key_input: KeyInput,
+        time_delta: Duration,
+    ) -> Result<StepOutcome> {
+        if self.breakpoints.contains(&self.state.pc) {
+            return Ok(StepOutcome::HitBreakpoint(self.state.pc));
+        }
+        self.step(key_input, time_delta)?;
+        Ok(StepOutcome::Stepped)
+    }
+
+    /// Starts or stops recording a [`TraceEntry`] for every instruction run, e.g. to compare
+    /// against a reference trace with [`crate::trace::diff_trace`]. Anything recorded so far is
+    /// kept until taken with [`EmulatedChip8::take_trace`].
+    pub fn enable_trace(&mut self, enabled: bool) {
+        self.trace_enabled = enabled;
+    }
+
+    /// Returns the trace recorded so far, oldest first, and starts a new one
+    pub fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.trace.drain(..).collect()
+    }
+
+    /// Makes [`EmulatedChip8::step_until_breakpoint`] stop whenever PC reaches `address`
+    pub fn add_breakpoint(&mut self, address: Address) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: Address) {
+        self.breakpoints.remove(&address);
+    }
+
+    /// Steps the emulator once for each entry in `schedule`, using it as the time since the
+    /// previous step, with no keys pressed. After each step `check` gets called with the total
+    /// emulated time so far and the resulting state, so tests can assert on time-dependent
+    /// behaviour at every point along the way.
+    #[cfg(test)]
+    pub fn step_on_schedule<I, F>(&mut self, schedule: I, mut check: F) -> Result
+    where
+        I: IntoIterator<Item = Duration>,
+        F: FnMut(Duration, &Chip8State),
+    {
+        let mut elapsed = Duration::ZERO;
+        for time_delta in schedule {
+            self.step(KeyInput::default(), time_delta)?;
+            elapsed += time_delta;
+            check(elapsed, &self.state);
+        }
+        Ok(())
+    }
+
+    /// The interpreter-specific behaviours the opcodes currently follow
+    pub fn quirks(&self) -> &QuirkConfig {
+        &self.state.quirks
+    }
+
+    /// Changes the quirks mid-run, e.g. to try out different settings on a ROM without restarting
+    /// it. Quirks only get looked at while an instruction runs, so the new ones apply from the
+    /// next [`EmulatedChip8::step`] on, and nothing already done gets redone.
+    pub fn set_quirks(&mut self, quirks: QuirkConfig) {
+        self.state.quirks = quirks;
+    }
+
+    /// Returns what's been executed so far, if coverage was enabled through
+    /// [`EmulatedChip8::with_coverage`]
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Returns the memory access counts so far, if enabled through
+    /// [`EmulatedChip8::with_heatmap`]
+    pub fn heatmap(&self) -> Option<&MemoryHeatmap> {
+        self.state.heatmap.as_ref()
+    }
+
+    /// Returns the displays recorded through [`EmulatedChip8::with_display_history`], oldest first
+    pub fn display_history(&self) -> &VecDeque<Display> {
+        &self.display_history
+    }
+
     /// Returns the underlying chip8 state for inspection, use, or display.
     pub fn get_state(&self) -> &Chip8State {
         &self.state
     }
 
-    fn update_timers(&mut self, time_delta: Duration) {
-        update_timer(
-            &mut self.state.delay_timer,
-            &mut self.state.since_last_delay_update,
-            time_delta,
-        );
-        update_timer(
-            &mut self.state.sound_timer,
-            &mut self.state.since_last_sound_update,
-            time_delta,
-        );
+    pub fn delay_timer(&self) -> u8 {
+        self.state.delay_timer.0
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.state.sound_timer.0
     }
 
-    fn fetch(&mut self) -> u16 {
-        let opcode_bytes = BigEndian::read_u16(&self.state.memory[self.state.pc.0.into()..]);
+    /// Overwrites the delay timer, e.g. to set up a timer-dependent scenario without running the
+    /// cycles it would take to get there. This doesn't move the shared 60Hz tick, so the first
+    /// decrement can come sooner than a full tick later.
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.state.delay_timer = Register(value);
+    }
+
+    /// Overwrites the sound timer. See [`EmulatedChip8::set_delay_timer`].
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.state.sound_timer = Register(value);
+    }
+
+    /// Freezes (or unfreezes) both timers while the CPU keeps stepping, to help tell apart bugs in
+    /// the program's logic from ones in its timing. No time builds up towards the next tick while
+    /// paused, so the timers pick up where they left off.
+    pub fn set_timers_paused(&mut self, paused: bool) {
+        self.timers_paused = paused;
+    }
+
+    /// Runs the diagnostics that only make sense once the program has stopped
+    fn check_exit_state(&mut self) {
+        if self.state.diagnostics.warn_on_stack_imbalance && !self.state.stack.is_empty() {
+            let stack = self.state.stack.clone();
+            self.state.warn(Warning::StackImbalance { stack });
+        }
+    }
+
+    /// Counts both timers down by however many 60Hz ticks fit in `elapsed`, plus whatever was left
+    /// over from last time. Like on the original hardware they're driven by the same tick, so two
+    /// timers set to the same value always reach zero together.
+    ///
+    /// [`EmulatedChip8::step`] calls this with its own `time_delta`. [`EmulatedChip8::run`] calls
+    /// it with real time instead, so the timers keep time whatever the instruction rate is.
+    pub fn tick_timers(&mut self, elapsed: Duration) {
+        if self.timers_paused {
+            return;
+        }
+        let ticks = timer_ticks(&mut self.state.since_last_timer_tick, elapsed);
+        decrement_timer(&mut self.state.delay_timer, ticks);
+        decrement_timer(&mut self.state.sound_timer, ticks);
+    }
+
+    fn advance_frame(&mut self, time_delta: Duration) {
+        for _ in 0..self.frame_clock.advance(time_delta) {
+            self.waiting_for_frame = false;
+            if let Some(frame_log) = &mut self.frame_log {
+                if let Err(e) = writeln!(frame_log, "{}", self.state.summary()) {
+                    // Not worth stopping the emulator over, but no point trying again either
+                    error!("failed to write to the frame log, disabling it: {e}");
+                    self.frame_log = None;
+                }
+            }
+            if let Some(frame_handler) = &mut self.frame_handler {
+                frame_handler(&self.state);
+            }
+        }
+    }
+
+    fn fetch(&mut self) -> Result<u16> {
+        let pc = self.state.pc;
+        if pc < PROGRAM_START {
+            match self.state.quirks.reserved_execution {
+                ReservedExecution::Warn => {
+                    self.state.warn(Warning::ReservedExecution { address: pc });
+                }
+                ReservedExecution::Error => return Err(Fault::ReservedExecution(pc).into()),
+                ReservedExecution::Allow => {}
+            }
+        }
+        let start = usize::from(pc);
+        let opcode_bytes = self
+            .state
+            .memory_slice(start..start + 2)
+            .map(BigEndian::read_u16)
+            .map_err(|_| Error::PcOutOfBounds(pc.0))?;
+        self.state.record_read(start, 2);
         // Always increment PC in fetch stage
         self.state.pc += 2;
-        opcode_bytes
+        Ok(opcode_bytes)
     }
 
     fn decode(&mut self, opcode_bytes: u16) -> OpCodeData {
@@ -141,11 +718,56 @@ impl EmulatedChip8 {
     }
 
     fn execute(&mut self, opcode_data: OpCodeData) -> Result<()> {
-        for instruction in &self.supported_instructions {
-            if opcode_data.full_opcode & instruction.opcode_mask() == instruction.opcode_val() {
-                debug!("Executing instruction {instruction:?} with opcode data {opcode_data:?}; pc: {:#x}", self.state.pc.0);
-                instruction.execute(&mut self.state, opcode_data);
-                return Ok(());
+        if let Some(instruction) = self.decode_table.lookup(opcode_data.full_opcode) {
+            debug!(
+                "Executing instruction {instruction:?} with opcode data {opcode_data:?}; pc: {:#x}",
+                self.state.pc.0
+            );
+            if let Some(coverage) = &mut self.coverage {
+                // PC has already moved past the instruction by now
+                coverage.record(Address(self.state.pc.0.wrapping_sub(2)), instruction);
+            }
+            let drew = opcode_data.full_opcode & 0xF000 == 0xD000;
+            // Until there's a recorded frame to compare against, compare against the display as
+            // it was before this instruction
+            let initial_display = (self.display_history_len > 0 && self.display_history.is_empty())
+                .then(|| self.state.display.clone());
+            instruction.execute(&mut self.state, opcode_data)?;
+            if self.one_draw_per_frame && drew {
+                self.waiting_for_frame = true;
+            }
+            if self.display_history_len > 0 {
+                let previous = initial_display.as_ref().or(self.display_history.back());
+                if previous != Some(&self.state.display) {
+                    if self.display_history.len() == self.display_history_len {
+                        self.display_history.pop_front();
+                    }
+                    self.display_history.push_back(self.state.display.clone());
+                }
+            }
+            return Ok(());
+        }
+
+        if self.skip_unknown_opcodes {
+            // PC was already moved past the opcode in the fetch stage, so we just carry on
+            warn!(
+                "Skipping unsupported opcode {:#06x}; pc: {:#x}",
+                opcode_data.full_opcode, self.state.pc.0
+            );
+            return Ok(());
+        }
+
+        if let Some(recovery) = &mut self.recovery {
+            let address = Address(self.state.pc.0.wrapping_sub(2));
+            match recovery(address, opcode_data.full_opcode) {
+                Recovery::Skip => {
+                    warn!(
+                        "Recovering from unsupported opcode {:#06x} at {address} by skipping it",
+                        opcode_data.full_opcode
+                    );
+                    return Ok(());
+                }
+                Recovery::Abort => {}
             }
         }
 
@@ -153,37 +775,32 @@ impl EmulatedChip8 {
     }
 }
 
-const DECREMENT_PERIOD: Duration = Duration::from_millis(17);
-
-fn update_timer(register: &mut Register, since_last_update: &mut Duration, time_delta: Duration) {
-    if register.0 > 0 {
-        // Target frequency at which we reduce is 60Hz (period ~16.67ms). To do that we need to
-        // consider two cases: time_delta > 16.67ms (in which case, we need to decrement multiple
-        // times), and time_delta < 16.67ms (in which case we need to keep track of how much time
-        // until the next time we decrement). We will handle both together by:
-        // - Adding the time delta to the time since last update
-        // - Remove decrement period from that new time since last update until we can no longer
-        // - Store back any reminder
-        // The period at which we decrement the timer is represented by `DECREMENT_PERIOD` (which
-        // we round up to 17ms for simplicity)
-
-        let mut new_since_last_update = *since_last_update + time_delta;
-        while new_since_last_update > DECREMENT_PERIOD {
-            if register.0 > 1 {
-                register.0 -= 1;
-                new_since_last_update -= DECREMENT_PERIOD;
-            }
-            // Special case: if we reach 1 and need to subtract again, we should just reset
-            // `since_last_update` and stop
-            else {
-                register.0 = 0;
-                *since_last_update = Duration::default();
-                return;
-            }
-        }
+/// Timers count down at 60Hz, in step with the display
+const DECREMENT_PERIOD: Duration = FRAME_PERIOD;
+
+/// Works out how many 60Hz timer ticks happen after `time_delta` more has passed, keeping track
+/// of the time left over towards the next tick in `since_last_tick`
+fn timer_ticks(since_last_tick: &mut Duration, time_delta: Duration) -> u32 {
+    // time_delta can be longer than a tick (in which case we decrement multiple times) or shorter
+    // (in which case we need to keep track of how much time there is until the next decrement).
+    // Both are handled by adding the time delta to what's been accumulated, seeing how many whole
+    // decrement periods fit into that, and storing back the remainder
+    let total = (*since_last_tick + time_delta).as_nanos();
+    let period = DECREMENT_PERIOD.as_nanos();
+    // The remainder is always shorter than a tick, so fits in a u64
+    *since_last_tick = Duration::from_nanos((total % period) as u64);
+    (total / period).try_into().unwrap_or(u32::MAX)
+}
+
+fn decrement_timer(register: &mut Register, ticks: u32) {
+    register.0 = register
+        .0
+        .saturating_sub(ticks.try_into().unwrap_or(u8::MAX));
+}
 
-        // new_since_last_update will contain the reminder of the "division" we made
-        *since_last_update = new_since_last_update;
+impl Default for EmulatedChip8 {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -193,20 +810,31 @@ impl fmt::Display for EmulatedChip8 {
     }
 }
 
+impl Default for Chip8State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Chip8State {
     pub fn new() -> Chip8State {
         Chip8State {
             memory: [0; 4096],
             display: Display::default(),
             pc: Address(0),
-            stack: VecDeque::new(),
+            stack: Stack::new(),
             index_register: Address(0),
             delay_timer: Register(0),
-            since_last_delay_update: Duration::default(),
             sound_timer: Register(0),
-            since_last_sound_update: Duration::default(),
+            since_last_timer_tick: Duration::default(),
             gp_registers: [Register(0); 16],
             key_state: KeyInput::default(),
+            rng: Chip8Rng::from_entropy(),
+            diagnostics: Diagnostics::default(),
+            quirks: QuirkConfig::default(),
+            warnings: Vec::new(),
+            last_collisions: Vec::new(),
+            heatmap: None,
         }
     }
 
@@ -223,7 +851,7 @@ impl Chip8State {
     }
 
     #[cfg(test)]
-    pub fn with_stack(mut self, stack: VecDeque<Address>) -> Chip8State {
+    pub fn with_stack(mut self, stack: Stack) -> Chip8State {
         self.stack = stack;
         self
     }
@@ -264,8 +892,33 @@ impl Chip8State {
         self
     }
 
+    #[cfg(test)]
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Chip8State {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_seed(mut self, seed: u64) -> Chip8State {
+        self.rng = Chip8Rng::from_seed(seed);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_quirks(mut self, quirks: QuirkConfig) -> Chip8State {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Returns a one line overview of the state: PC, I, a few registers, the timers and how many
+    /// pixels are lit
+    pub fn summary(&self) -> StateSummary<'_> {
+        StateSummary(self)
+    }
+
+    /// Whether a key is pressed. Only the low nibble of `key` is used, as there are just 16 keys
     pub fn is_pressed(&self, key: u8) -> bool {
-        self.key_state.key_state[usize::from(key)]
+        self.key_state.key_state[usize::from(key & 0x0F)]
     }
 
     pub fn memory_set(&mut self, bytes: &[u8], start: Address) {
@@ -273,15 +926,161 @@ impl Chip8State {
         let byte_end = byte_start + bytes.len();
         // If byte_end is *exactly* 0x1000 we can still write (as the end is one past the last
         // element), but if we go over that we're writing past the end
-        if byte_end > 0x1000 {
-            panic!("asking to write past last byte");
+        self.memory_slice_mut(byte_start..byte_end)
+            .expect("asking to write past last byte")
+            .copy_from_slice(bytes);
+        self.record_write(byte_start, bytes.len());
+    }
+
+    /// Borrows the bytes of memory in `range`, or fails with [`Error::AddressOutOfRange`] if any
+    /// of it is past the end of memory. Unlike indexing into [`Chip8State::memory`] this never
+    /// panics, so it's the way to read several bytes at addresses the program controls.
+    pub fn memory_slice(&self, range: Range<usize>) -> Result<&[u8]> {
+        let error = Error::AddressOutOfRange(Address(range.start as u16));
+        self.memory.get(range).ok_or(error)
+    }
+
+    /// Mutable version of [`Chip8State::memory_slice`]
+    pub fn memory_slice_mut(&mut self, range: Range<usize>) -> Result<&mut [u8]> {
+        let error = Error::AddressOutOfRange(Address(range.start as u16));
+        self.memory.get_mut(range).ok_or(error)
+    }
+
+    /// Hash of the whole state, for quickly checking whether two states are identical
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Like [`Chip8State::state_hash`], but leaving out the fields that depend on real time or
+    /// randomness: the time towards the next timer tick and the RNG state. Two runs of a
+    /// deterministic ROM should always end up with the same stable hash, even if their timing
+    /// wasn't exactly the same.
+    pub fn stable_hash(&self) -> u64 {
+        // Destructured so that adding a field forces a decision on whether it's stable
+        let Chip8State {
+            memory,
+            display,
+            pc,
+            stack,
+            index_register,
+            delay_timer,
+            sound_timer,
+            since_last_timer_tick: _,
+            gp_registers,
+            key_state,
+            rng: _,
+            diagnostics,
+            quirks,
+            warnings,
+            last_collisions,
+            heatmap,
+        } = self;
+        let mut hasher = StableHasher::default();
+        memory.hash(&mut hasher);
+        display.hash(&mut hasher);
+        pc.hash(&mut hasher);
+        stack.hash(&mut hasher);
+        index_register.hash(&mut hasher);
+        delay_timer.hash(&mut hasher);
+        sound_timer.hash(&mut hasher);
+        gp_registers.hash(&mut hasher);
+        key_state.hash(&mut hasher);
+        diagnostics.hash(&mut hasher);
+        quirks.hash(&mut hasher);
+        warnings.hash(&mut hasher);
+        last_collisions.hash(&mut hasher);
+        heatmap.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Reads the byte `offset` bytes past I. Every opcode reading memory through I should go
+    /// through here, so they all handle running off the end of memory the same way: wrapping
+    /// around with [`QuirkConfig::index_wraps`] on, and failing with
+    /// [`Fault::IndexOutOfRange`] otherwise.
+    pub fn mem_at_index(&mut self, offset: u16) -> Result<u8, Fault> {
+        let address = self.index_address(offset)?;
+        self.record_read(address, 1);
+        Ok(self.memory[address])
+    }
+
+    /// Writes `value` to the byte `offset` bytes past I. See [`Chip8State::mem_at_index`].
+    pub fn set_mem_at_index(&mut self, offset: u16, value: u8) -> Result<(), Fault> {
+        let address = self.index_address(offset)?;
+        self.record_write(address, 1);
+        self.memory[address] = value;
+        Ok(())
+    }
+
+    fn index_address(&self, offset: u16) -> Result<usize, Fault> {
+        let address = usize::from(self.index_register.0) + usize::from(offset);
+        if self.quirks.index_wraps {
+            // Addresses are 12 bits wide
+            Ok(address & 0x0FFF)
+        } else if address < self.memory.len() {
+            Ok(address)
+        } else {
+            Err(Fault::IndexOutOfRange {
+                index: self.index_register,
+                offset,
+            })
+        }
+    }
+
+    /// Notes down a read of memory for the [`MemoryHeatmap`], if there is one. Anything reading
+    /// memory on behalf of the program should call this.
+    pub fn record_read(&mut self, start: usize, len: usize) {
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_read(start, len);
+        }
+    }
+
+    /// Notes down a write to memory for the [`MemoryHeatmap`], if there is one. Writes through
+    /// [`Chip8State::memory_set`] are recorded already.
+    pub fn record_write(&mut self, start: usize, len: usize) {
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_write(start, len);
         }
-        self.memory[byte_start..byte_end].copy_from_slice(bytes);
     }
 
     pub fn gp_register(&mut self, index: u8) -> &mut Register {
         &mut self.gp_registers[index as usize]
     }
+
+    /// Logs a diagnostic warning and records it, unless we've already seen it before
+    pub fn warn(&mut self, warning: Warning) {
+        if !self.warnings.contains(&warning) {
+            warn!("{warning}");
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// Compact, single line overview of a [`Chip8State`], see [`Chip8State::summary`]
+pub struct StateSummary<'a>(&'a Chip8State);
+
+impl fmt::Display for StateSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0;
+        let lit = state
+            .display
+            .rows()
+            .flatten()
+            .filter(|pixel| **pixel)
+            .count();
+        write!(
+            f,
+            "pc={} i={} v0={} v1={} vf={} dt={} st={} lit={lit}",
+            state.pc,
+            state.index_register,
+            state.gp_registers[0x0],
+            state.gp_registers[0x1],
+            state.gp_registers[0xF],
+            state.delay_timer,
+            state.sound_timer,
+        )
+    }
 }
 
 impl fmt::Display for Chip8State {
@@ -294,12 +1093,7 @@ impl fmt::Display for Chip8State {
         writeln!(f)?;
         writeln!(f, "PC: {}    DT: {}", self.pc, self.delay_timer)?;
         writeln!(f, "IR: {}    ST: {}", self.index_register, self.sound_timer)?;
-        write!(f, "Stack: [")?;
-        for (idx, val) in self.stack.iter().enumerate() {
-            let space = if idx == self.stack.len() - 1 { "" } else { " " };
-            write!(f, "{val}{space}")?;
-        }
-        writeln!(f, "]")?;
+        writeln!(f, "Stack: {}", self.stack)?;
         writeln!(f, "Memory:")?;
         for (idx, byte) in self.memory.iter().enumerate() {
             write!(f, "{byte:02x} ")?;
@@ -312,6 +1106,7 @@ impl fmt::Display for Chip8State {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Address(pub u16);
 
 impl From<Address> for usize {
@@ -333,6 +1128,7 @@ impl std::ops::AddAssign<u16> for Address {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Register(pub u8);
 
 impl fmt::Display for Register {
@@ -349,8 +1145,28 @@ impl std::ops::AddAssign<u8> for Register {
 
 #[cfg(test)]
 mod test {
-    use super::EmulatedChip8;
-    use crate::opcodes::OpCodeData;
+    use super::{
+        Address, Chip8Rng, Chip8State, EmulatedChip8, Error, Fault, KeyInput, Recovery, Register,
+        RegisterSnapshot, StepOutcome,
+    };
+    use crate::{
+        diagnostics::{Diagnostics, Warning},
+        display::Display,
+        font::Chip8Font,
+        opcodes::OpCodeData,
+        program::Program,
+        quirks::{QuirkConfig, ReservedExecution, ShiftFlagOrder},
+        renderer::{Command, NullRenderer, Renderer},
+        save_state,
+        stack::STACK_DEPTH,
+        timing::FRAME_PERIOD,
+    };
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use test_case::test_case;
 
     #[test]
     fn test_decode() {
@@ -369,4 +1185,835 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_first_pressed() {
+        let mut key_input = KeyInput::default();
+        assert_eq!(key_input.first_pressed(), None);
+        key_input.key_state[0x9] = true;
+        key_input.key_state[0x3] = true;
+        // The lowest key wins, whichever was pressed first
+        assert_eq!(key_input.first_pressed(), Some(0x3));
+    }
+
+    #[test]
+    fn test_set_pc() {
+        // LD V0, 0x01; LD V0, 0x02
+        let program = Program::new_from_data(&[0x60, 0x01, 0x60, 0x02]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.set_pc(Address(0x202)).unwrap();
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert_eq!(chip.state.gp_registers[0], Register(0x02));
+        assert_eq!(chip.state.pc, Address(0x204));
+
+        assert!(matches!(
+            chip.set_pc(Address(0xFFF)),
+            Err(Error::AddressOutOfRange(Address(0xFFF)))
+        ));
+        assert_eq!(chip.state.pc, Address(0x204));
+    }
+
+    #[test]
+    fn test_memory_slice() {
+        let mut state = Chip8State::new().with_memory_set(&[0x12, 0x34], Address(0x300));
+        assert_eq!(state.memory_slice(0x300..0x302).unwrap(), &[0x12, 0x34]);
+        assert_eq!(state.memory_slice(0x300..0x300).unwrap(), &[] as &[u8]);
+        // Right up to the last byte is fine, one more isn't
+        assert_eq!(state.memory_slice(0xFFE..0x1000).unwrap(), &[0x00, 0x00]);
+        assert!(matches!(
+            state.memory_slice(0xFFF..0x1001),
+            Err(Error::AddressOutOfRange(Address(0xFFF)))
+        ));
+        assert!(matches!(
+            state.memory_slice(0x1000..0x1001),
+            Err(Error::AddressOutOfRange(Address(0x1000)))
+        ));
+
+        state.memory_slice_mut(0xFFF..0x1000).unwrap()[0] = 0xAB;
+        assert_eq!(state.memory[0xFFF], 0xAB);
+        assert!(state.memory_slice_mut(0xFFF..0x1001).is_err());
+    }
+
+    #[test]
+    fn test_fetch_past_end_of_memory() {
+        // JP 0xFFF, leaving only one byte of the next opcode in memory
+        let program = Program::new_from_data(&[0x1F, 0xFF]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert!(matches!(
+            chip.step(KeyInput::default(), Duration::ZERO),
+            Err(Error::PcOutOfBounds(0xFFF))
+        ));
+        // Nothing got run
+        assert_eq!(chip.get_state().pc, Address(0xFFF));
+    }
+
+    #[test]
+    fn test_set_register_const_tight_loop() {
+        // LD V0, 0x10; LD V1, 0x11; ...; LD VE, 0x1E; JP 0x200
+        let mut program: Vec<u8> = (0..0xF).flat_map(|x| [0x60 | x, 0x10 | x]).collect();
+        program.extend([0x12, 0x00]);
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&Program::new_from_data(&program).unwrap());
+
+        // Stop on the jump after many passes of the loop, with every register set along the way
+        for _ in 0..16 * 1000 + 15 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+        assert_eq!(chip.get_state().pc, Address(0x21E));
+        for x in 0..0xF {
+            assert_eq!(chip.get_state().gp_registers[x], Register(0x10 | x as u8));
+        }
+    }
+
+    #[test]
+    fn test_emulators_share_decode_table() {
+        let chip = EmulatedChip8::new();
+        let other_chip = EmulatedChip8::default();
+        assert!(std::ptr::eq(chip.decode_table, other_chip.decode_table));
+    }
+
+    #[test]
+    fn test_run_stops_with_renderer() {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.run(NullRenderer::default().with_frame_limit(2), 10_000.)
+            .unwrap();
+    }
+
+    /// Asks to speed up once, then records every speed the emulator reports until it's been sent
+    /// enough frames
+    struct SpeedRecorder {
+        frames: usize,
+        sped_up: bool,
+        speeds: Arc<Mutex<Vec<f64>>>,
+    }
+
+    impl Renderer for SpeedRecorder {
+        fn new(_render_period: Duration) -> anyhow::Result<SpeedRecorder> {
+            unimplemented!()
+        }
+
+        fn terminated(&self) -> bool {
+            self.frames >= 30
+        }
+
+        fn current_key_state(&self) -> KeyInput {
+            KeyInput::default()
+        }
+
+        fn next_command(&mut self) -> Option<Command> {
+            (!std::mem::replace(&mut self.sped_up, true)).then_some(Command::SpeedUp)
+        }
+
+        fn paused(&self) -> bool {
+            false
+        }
+
+        fn update_screen(&mut self, _display: &Display) -> anyhow::Result<()> {
+            self.frames += 1;
+            Ok(())
+        }
+
+        fn update_sound_timer(&mut self, _sound_timer: Register) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn set_buzzer(&mut self, _on: bool) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn update_speed(&mut self, speed: f64) -> anyhow::Result<()> {
+            self.speeds.lock().unwrap().push(speed);
+            Ok(())
+        }
+
+        fn update_registers(&mut self, _registers: &RegisterSnapshot) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test_case(Duration::ZERO, false; "instant")]
+    #[test_case(Duration::from_millis(50), true; "ramped")]
+    fn test_speed_ramp(speed_ramp: Duration, eases: bool) {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new().with_speed_ramp(speed_ramp);
+        chip.load_program(&program);
+        let speeds = Arc::new(Mutex::new(vec![]));
+        let renderer = SpeedRecorder {
+            frames: 0,
+            sped_up: false,
+            speeds: speeds.clone(),
+        };
+        chip.run(renderer, 1000.).unwrap();
+
+        // Half a second is plenty to settle on 1250Hz, but with a ramp we should pass through
+        // speeds in between on the way there
+        let speeds = speeds.lock().unwrap();
+        assert_eq!(speeds.first(), Some(&1000.));
+        assert_eq!(speeds.last(), Some(&1250.));
+        assert!(speeds.windows(2).all(|pair| pair[0] <= pair[1]));
+        let in_between = speeds.iter().any(|speed| 1000. < *speed && *speed < 1250.);
+        assert_eq!(in_between, eases);
+    }
+
+    // CALL 0x204; JP 0x202; JP 0x204
+    #[test_case(&[0x22, 0x04, 0x12, 0x02, 0x12, 0x04], true; "inside_subroutine")]
+    // CALL 0x204; JP 0x202; RET
+    #[test_case(&[0x22, 0x04, 0x12, 0x02, 0x00, 0xEE], false; "balanced")]
+    fn test_warn_on_stack_imbalance(program: &[u8], warns: bool) {
+        let program = Program::new_from_data(program).unwrap();
+        let mut chip = EmulatedChip8::new().with_diagnostics(Diagnostics {
+            warn_on_stack_imbalance: true,
+            ..Diagnostics::default()
+        });
+        chip.load_program(&program);
+        chip.run(NullRenderer::default().with_frame_limit(2), 10_000.)
+            .unwrap();
+
+        let expected = [Address(0x202)].into_iter().collect();
+        assert_eq!(
+            chip.get_state().warnings,
+            if warns {
+                vec![Warning::StackImbalance { stack: expected }]
+            } else {
+                vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_history() {
+        // LD I, 0x20A; DRW V0, V0, 1; ADD V0, 1; JP 0x202; (sprite) 0x80
+        let program = Program::new_from_data(&[
+            0xA2, 0x0A, 0xD0, 0x01, 0x70, 0x01, 0x12, 0x02, 0x00, 0x00, 0x80,
+        ])
+        .unwrap();
+        let mut chip = EmulatedChip8::new().with_display_history(3);
+        chip.load_program(&program);
+
+        // Each pass of the loop is 3 instructions, so this draws 5 times
+        for _ in 0..16 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+        let history = chip.display_history();
+        assert_eq!(history.len(), 3);
+        // Only the last 3 draws are kept, each adding a pixel further along the diagonal
+        for (frame, display) in history.iter().enumerate() {
+            let lit: Vec<usize> = (0..5).filter(|idx| display.pixels[*idx][*idx]).collect();
+            assert_eq!(lit, (0..frame + 3).collect::<Vec<_>>());
+        }
+        assert_eq!(history.back(), Some(&chip.get_state().display));
+    }
+
+    #[test]
+    fn test_display_history_records_every_change() {
+        // CLS; LD I, 0x20C; DRW V0, V0, 1; SCD 2; HIGH; LOW; (sprite) 0x80
+        let program = Program::new_from_data(&[
+            0x00, 0xE0, 0xA2, 0x0C, 0xD0, 0x01, 0x00, 0xC2, 0x00, 0xFF, 0x00, 0xFE, 0x80,
+        ])
+        .unwrap();
+        let mut chip = EmulatedChip8::new().with_display_history(8);
+        chip.load_program(&program);
+
+        for _ in 0..6 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+        // Clearing an already blank display changes nothing, but the draw, the scroll and both
+        // resolution switches each leave a frame behind
+        let history = chip.display_history();
+        assert_eq!(history.len(), 4);
+        assert!(history[0].pixels[0][0]);
+        assert!(history[1].pixels[1][0]);
+        assert_eq!(history.back(), Some(&chip.get_state().display));
+    }
+
+    #[test]
+    fn test_one_draw_per_frame() {
+        // DRW V0, V0, 1; ADD V1, 0x01; JP 0x200
+        let program = Program::new_from_data(&[0xD0, 0x01, 0x71, 0x01, 0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new().with_one_draw_per_frame(true);
+        chip.load_program(&program);
+
+        // 10 steps per frame would be enough for 3 trips around the loop without the lock
+        let period = FRAME_PERIOD / 10;
+        for frame in 0..5 {
+            let mut draws = 0;
+            for _ in 0..10 {
+                let drawing = chip.get_state().pc == Address(0x200);
+                chip.step(KeyInput::default(), period).unwrap();
+                if drawing && chip.get_state().pc == Address(0x202) {
+                    draws += 1;
+                }
+            }
+            assert_eq!(draws, 1, "frame {frame}");
+        }
+    }
+
+    /// Frame log sink the test can still read from after handing it to the emulator
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_frame_log() {
+        // LD V0, 0x2A; JP 0x202
+        let program = Program::new_from_data(&[0x60, 0x2A, 0x12, 0x02]).unwrap();
+        let log = SharedLog::default();
+        let mut chip = EmulatedChip8::new().with_frame_log(Box::new(log.clone()));
+        chip.load_program(&program);
+
+        // 3 frames, at 5 steps per frame
+        for _ in 0..15 {
+            chip.step(
+                KeyInput::default(),
+                FRAME_PERIOD / 5 + Duration::from_nanos(1),
+            )
+            .unwrap();
+        }
+
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[2],
+            "pc=0x0202 i=0x0000 v0=0x2a v1=0x00 vf=0x00 dt=0x00 st=0x00 lit=0"
+        );
+    }
+
+    #[test_case(FRAME_PERIOD / 5 + Duration::from_nanos(1), 15, 3; "several_steps_per_frame")]
+    #[test_case(FRAME_PERIOD, 4, 4; "one_step_per_frame")]
+    #[test_case(FRAME_PERIOD * 2, 3, 6; "several_frames_per_step")]
+    fn test_frame_handler(time_delta: Duration, steps: usize, frames: usize) {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let fired = Arc::new(Mutex::new(0));
+        let fired_clone = fired.clone();
+        let mut chip = EmulatedChip8::new().with_frame_handler(Box::new(move |_| {
+            *fired_clone.lock().unwrap() += 1;
+        }));
+        chip.load_program(&program);
+
+        for _ in 0..steps {
+            chip.step(KeyInput::default(), time_delta).unwrap();
+        }
+        assert_eq!(*fired.lock().unwrap(), frames);
+    }
+
+    #[test]
+    fn test_set_delay_timer() {
+        // `LD V3, DT`
+        let program = Program::new_from_data(&[0xF3, 0x07]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.set_delay_timer(0x2A);
+        chip.set_sound_timer(0x10);
+        assert_eq!(chip.delay_timer(), 0x2A);
+        assert_eq!(chip.sound_timer(), 0x10);
+
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert_eq!(chip.get_state().gp_registers[0x3], Register(0x2A));
+    }
+
+    #[test]
+    fn test_timers_independent_of_steps() {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.set_delay_timer(60);
+
+        // Instructions alone don't move the timers on
+        let period = Duration::from_secs_f64(1. / 10_000.);
+        for _ in 0..1000 {
+            chip.step_cpu(KeyInput::default(), period).unwrap();
+        }
+        assert_eq!(chip.get_state().delay_timer, Register(60));
+
+        // Only the time passed to tick_timers does, at 60Hz whatever the instruction rate
+        let mut elapsed = Duration::ZERO;
+        while chip.get_state().delay_timer.0 > 0 {
+            chip.step_cpu(KeyInput::default(), period).unwrap();
+            chip.tick_timers(period);
+            elapsed += period;
+        }
+        assert!(
+            (elapsed.as_secs_f64() - 1.).abs() < 0.001,
+            "took {elapsed:?}"
+        );
+    }
+
+    #[test]
+    fn test_timers_paused() {
+        // LD V0, 0x2A; JP 0x202
+        let program = Program::new_from_data(&[0x60, 0x2A, 0x12, 0x02]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.set_delay_timer(0x10);
+        chip.set_timers_paused(true);
+
+        chip.step(KeyInput::default(), FRAME_PERIOD * 3).unwrap();
+        assert_eq!(chip.get_state().pc, Address(0x202));
+        assert_eq!(chip.get_state().gp_registers[0x0], Register(0x2A));
+        assert_eq!(chip.delay_timer(), 0x10);
+
+        chip.set_timers_paused(false);
+        chip.step(KeyInput::default(), FRAME_PERIOD * 3).unwrap();
+        assert_eq!(chip.delay_timer(), 0x0D);
+    }
+
+    #[test_case(Duration::from_millis(1); "1ms_steps")]
+    #[test_case(Duration::from_millis(7); "7ms_steps")]
+    #[test_case(FRAME_PERIOD; "frame_steps")]
+    fn test_delay_timer_counts_down_at_60hz(time_delta: Duration) {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.set_delay_timer(60);
+
+        let steps = Duration::from_secs(1)
+            .as_nanos()
+            .div_ceil(time_delta.as_nanos());
+        let schedule = std::iter::repeat_n(time_delta, steps as usize);
+        chip.step_on_schedule(schedule, |elapsed, state| {
+            let ticks = elapsed.as_nanos() / FRAME_PERIOD.as_nanos();
+            let expected = 60u8.saturating_sub(ticks.try_into().unwrap_or(u8::MAX));
+            assert_eq!(
+                state.delay_timer,
+                Register(expected),
+                "wrong delay timer after {elapsed:?}"
+            );
+        })
+        .unwrap();
+        assert_eq!(chip.delay_timer(), 0);
+    }
+
+    #[test]
+    fn test_timers_count_down_in_lockstep() {
+        // JP 0x200
+        let program = Program::new_from_data(&[0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        // Start the sound timer part way through a tick of the delay timer
+        chip.set_delay_timer(40);
+        chip.step_on_schedule([Duration::from_millis(25)], |_, _| {})
+            .unwrap();
+        chip.set_sound_timer(chip.delay_timer());
+
+        let mut zero_at = None;
+        chip.step_on_schedule(
+            std::iter::repeat_n(Duration::from_millis(3), 300),
+            |elapsed, state| {
+                assert_eq!(
+                    state.delay_timer, state.sound_timer,
+                    "drifted after {elapsed:?}"
+                );
+                if state.delay_timer.0 == 0 && zero_at.is_none() {
+                    zero_at = Some(elapsed);
+                }
+            },
+        )
+        .unwrap();
+        assert!(zero_at.is_some());
+    }
+
+    #[test]
+    fn test_stable_hash_ignores_volatile_fields() {
+        let state = Chip8State::new().with_register(Register(0x12), 0x3);
+        let mut other = state.clone();
+        other.since_last_timer_tick = Duration::from_millis(5);
+        other.rng = Chip8Rng::from_seed(42);
+        assert_eq!(state.stable_hash(), other.stable_hash());
+        assert_ne!(state.state_hash(), other.state_hash());
+
+        other.delay_timer = Register(0x01);
+        assert_ne!(state.stable_hash(), other.stable_hash());
+    }
+
+    #[test_case(ReservedExecution::Warn; "warn")]
+    #[test_case(ReservedExecution::Error; "error")]
+    #[test_case(ReservedExecution::Allow; "allow")]
+    fn test_reserved_execution(reserved_execution: ReservedExecution) {
+        // JP 0x050, into the font. The font isn't made of valid instructions, so skip those
+        let program = Program::new_from_data(&[0x10, 0x50]).unwrap();
+        let mut chip = EmulatedChip8::new()
+            .with_skip_unknown_opcodes(true)
+            .with_quirks(QuirkConfig {
+                reserved_execution,
+                ..QuirkConfig::default()
+            });
+        chip.write_font(&Chip8Font::new_from_default().unwrap());
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert!(chip.get_state().warnings.is_empty());
+
+        let result = chip.step(KeyInput::default(), Duration::ZERO);
+        match reserved_execution {
+            ReservedExecution::Warn => {
+                result.unwrap();
+                assert_eq!(
+                    chip.get_state().warnings,
+                    vec![Warning::ReservedExecution {
+                        address: Address(0x050)
+                    }]
+                );
+            }
+            ReservedExecution::Error => assert!(matches!(
+                result,
+                Err(Error::Fault(Fault::ReservedExecution(Address(0x050))))
+            )),
+            ReservedExecution::Allow => {
+                result.unwrap();
+                assert!(chip.get_state().warnings.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_quirks() {
+        // LD VF, 0x06; SHR VF; LD VF, 0x06; SHR VF
+        let program =
+            Program::new_from_data(&[0x6F, 0x06, 0x8F, 0x06, 0x6F, 0x06, 0x8F, 0x06]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        assert_eq!(chip.quirks().shift_flag_order, ShiftFlagOrder::FlagLast);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        // VF holds the shifted out bit
+        assert_eq!(chip.get_state().gp_registers[0xF], Register(0x00));
+
+        chip.set_quirks(QuirkConfig {
+            shift_flag_order: ShiftFlagOrder::ResultLast,
+            ..*chip.quirks()
+        });
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        // VF holds the shifted value
+        assert_eq!(chip.get_state().gp_registers[0xF], Register(0x03));
+    }
+
+    #[test]
+    fn test_fault_stops_step() {
+        // LD V2, 0x25; SKP V2
+        let program = Program::new_from_data(&[0x62, 0x25, 0xE2, 0x9E]).unwrap();
+        let mut chip = EmulatedChip8::new().with_quirks(QuirkConfig {
+            strict_key_values: true,
+            ..QuirkConfig::default()
+        });
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert!(matches!(
+            chip.step(KeyInput::default(), Duration::ZERO),
+            Err(Error::Fault(Fault::KeyOutOfRange(0x25)))
+        ));
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        // CALL 0x200, recursing forever
+        let program = Program::new_from_data(&[0x22, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        for _ in 0..STACK_DEPTH {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+        assert!(matches!(
+            chip.step(KeyInput::default(), Duration::ZERO),
+            Err(Error::Fault(Fault::StackOverflow(Address(0x200))))
+        ));
+        assert_eq!(chip.get_state().stack.len(), STACK_DEPTH);
+    }
+
+    #[test]
+    fn test_stack_underflow() {
+        // RET
+        let program = Program::new_from_data(&[0x00, 0xEE]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        assert!(matches!(
+            chip.step(KeyInput::default(), Duration::ZERO),
+            Err(Error::Fault(Fault::StackUnderflow(Address(0x200))))
+        ));
+    }
+
+    #[test]
+    fn test_skip_unknown_opcodes() {
+        // 0x0123 is a machine code routine call, which we don't support. It's followed by a
+        // valid `LD V0, 0x05`
+        let program = Program::new_from_data(&[0x01, 0x23, 0x60, 0x05]).unwrap();
+        let period = Duration::from_millis(1);
+
+        let mut strict_chip = EmulatedChip8::new();
+        strict_chip.load_program(&program);
+        assert!(matches!(
+            strict_chip.step(KeyInput::default(), period),
+            Err(Error::UnsupportedOpcode(0x0123))
+        ));
+
+        let mut chip = EmulatedChip8::new().with_skip_unknown_opcodes(true);
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), period).unwrap();
+        chip.step(KeyInput::default(), period).unwrap();
+        assert_eq!(chip.get_state().gp_registers[0x0], Register(0x05));
+    }
+
+    #[test]
+    fn test_recovery_skip() {
+        // 0x0123 and 0x0456 are unsupported machine code calls, around a valid `LD V0, 0x05`
+        let program = Program::new_from_data(&[0x01, 0x23, 0x60, 0x05, 0x04, 0x56]).unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let mut chip = EmulatedChip8::new().with_recovery(Box::new(move |address, opcode| {
+            seen_clone.lock().unwrap().push((address, opcode));
+            // Only skip the first one
+            if opcode == 0x0123 {
+                Recovery::Skip
+            } else {
+                Recovery::Abort
+            }
+        }));
+        chip.load_program(&program);
+
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert_eq!(chip.get_state().gp_registers[0x0], Register(0x05));
+        assert!(matches!(
+            chip.step(KeyInput::default(), Duration::ZERO),
+            Err(Error::UnsupportedOpcode(0x0456))
+        ));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(Address(0x200), 0x0123), (Address(0x204), 0x0456)]
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        // LD V0, 0x01; LD V1, 0x02; LD V2, 0x03; JP 0x200
+        let program =
+            Program::new_from_data(&[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new().with_trace_limit(3);
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        chip.enable_trace(true);
+        for _ in 0..4 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+        chip.enable_trace(false);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+
+        // Only the last 3 of the 4 traced instructions are kept
+        let trace = chip.take_trace();
+        let pcs: Vec<_> = trace.iter().map(|entry| entry.pc).collect();
+        assert_eq!(pcs, vec![Address(0x204), Address(0x206), Address(0x200)]);
+        assert_eq!(trace[0].registers[..3], [0x01, 0x02, 0x00]);
+        assert_eq!(trace[1].mnemonic(), "JP 0x200");
+        assert!(chip.take_trace().is_empty());
+    }
+
+    #[test]
+    fn test_step_until_breakpoint() {
+        // LD V0, 0x01; LD V1, 0x02; LD V2, 0x03; JP 0x200
+        let program =
+            Program::new_from_data(&[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x12, 0x00]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.add_breakpoint(Address(0x204));
+
+        let mut outcomes = Vec::new();
+        for _ in 0..4 {
+            outcomes.push(
+                chip.step_until_breakpoint(KeyInput::default(), Duration::ZERO)
+                    .unwrap(),
+            );
+        }
+        assert_eq!(
+            outcomes,
+            vec![
+                StepOutcome::Stepped,
+                StepOutcome::Stepped,
+                StepOutcome::HitBreakpoint(Address(0x204)),
+                StepOutcome::HitBreakpoint(Address(0x204)),
+            ]
+        );
+        assert_eq!(chip.get_state().gp_registers[0x2], Register(0));
+
+        // Stepping normally gets past it, and without the breakpoint the loop carries on through
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert_eq!(chip.get_state().gp_registers[0x2], Register(0x03));
+        chip.remove_breakpoint(Address(0x204));
+        for _ in 0..4 {
+            assert_eq!(
+                chip.step_until_breakpoint(KeyInput::default(), Duration::ZERO)
+                    .unwrap(),
+                StepOutcome::Stepped
+            );
+        }
+        assert_eq!(chip.get_state().pc, Address(0x206));
+    }
+
+    #[test]
+    fn test_reset() {
+        // LD V0, 0x05; LD DT, V0; CALL 0x208; (unused) 0x0000; LD I, 0x300
+        let data = [0x60, 0x05, 0xF0, 0x15, 0x22, 0x08, 0x00, 0x00, 0xA3, 0x00];
+        let program = Program::new_from_data(&data).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.write_font(&Chip8Font::new_from_default().unwrap());
+        chip.load_program(&program);
+        for _ in 0..4 {
+            chip.step(KeyInput::default(), Duration::from_millis(5))
+                .unwrap();
+        }
+        assert_eq!(chip.get_state().pc, Address(0x20A));
+        let memory = chip.get_state().memory;
+
+        chip.reset();
+        let state = chip.get_state();
+        assert_eq!(state.pc, Address(0x200));
+        assert_eq!(state.gp_registers, [Register(0); 16]);
+        assert_eq!(state.index_register, Address(0));
+        assert_eq!(state.delay_timer, Register(0));
+        assert_eq!(state.since_last_timer_tick, Duration::ZERO);
+        assert!(state.stack.is_empty());
+        assert_eq!(state.memory, memory);
+        assert_eq!(&state.memory[0x200..0x20A], &data);
+
+        // The program runs again from the top
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        assert_eq!(chip.get_state().gp_registers[0x0], Register(0x05));
+
+        chip.reset_hard();
+        assert_eq!(chip.get_state().pc, Address(0x200));
+        assert!(chip.get_state().memory.iter().all(|byte| *byte == 0));
+        assert!(matches!(
+            chip.save_slot(1),
+            Err(save_state::Error::NoProgramLoaded)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_save_state_round_trip() {
+        // LD V0, 0x05; LD DT, V0; LD V1, 0x07; DRW V0, V0, 5
+        let program =
+            Program::new_from_data(&[0x60, 0x05, 0xF0, 0x15, 0x61, 0x07, 0xD0, 0x05]).unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::from_millis(5))
+            .unwrap();
+        chip.step(KeyInput::default(), Duration::from_millis(5))
+            .unwrap();
+        let saved_state = chip.get_state().clone();
+        assert_eq!(saved_state.delay_timer, Register(0x05));
+        assert_ne!(saved_state.since_last_timer_tick, Duration::ZERO);
+        let bytes = chip.save_state();
+
+        let mut key_input = KeyInput::default();
+        key_input.key_state[0xA] = true;
+        chip.step(key_input.clone(), Duration::from_millis(5))
+            .unwrap();
+        chip.step(key_input, Duration::from_millis(5)).unwrap();
+        assert_ne!(chip.get_state(), &saved_state);
+
+        chip.load_state(&bytes).unwrap();
+        assert_eq!(chip.get_state(), &saved_state);
+        assert!(matches!(
+            chip.load_state(&bytes[..bytes.len() / 2]),
+            Err(save_state::Error::InvalidData(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load_state_keeps_setup() {
+        // LD V0, 0x05
+        let program = Program::new_from_data(&[0x60, 0x05]).unwrap();
+        let mut saving_chip = EmulatedChip8::new();
+        saving_chip.load_program(&program);
+        saving_chip
+            .step(KeyInput::default(), Duration::ZERO)
+            .unwrap();
+        let bytes = saving_chip.save_state();
+
+        let quirks = QuirkConfig {
+            cls_resets_vf: true,
+            ..QuirkConfig::default()
+        };
+        let diagnostics = Diagnostics {
+            report_collisions: true,
+            ..Diagnostics::default()
+        };
+        let mut chip = EmulatedChip8::new()
+            .with_quirks(quirks)
+            .with_diagnostics(diagnostics)
+            .with_heatmap(true);
+        chip.load_program(&program);
+        chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        let heatmap = chip.heatmap().cloned();
+        chip.load_state(&bytes).unwrap();
+
+        assert_eq!(chip.get_state().gp_registers[0x0], Register(0x05));
+        assert_eq!(chip.get_state().quirks, quirks);
+        assert_eq!(chip.get_state().diagnostics, diagnostics);
+        assert!(heatmap.is_some());
+        assert_eq!(chip.heatmap().cloned(), heatmap);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_save_slot_round_trip() {
+        let save_dir = std::env::temp_dir().join(format!("chip8_slots_{}", std::process::id()));
+        std::fs::create_dir_all(&save_dir).unwrap();
+
+        // LD V0, 0x01; LD V0, 0x02
+        let program = Program::new_from_data(&[0x60, 0x01, 0x60, 0x02]).unwrap();
+        let period = Duration::from_millis(1);
+        let mut chip = EmulatedChip8::new().with_save_dir(save_dir.clone());
+        chip.load_program(&program);
+
+        chip.step(KeyInput::default(), period).unwrap();
+        let saved_state = chip.get_state().clone();
+        chip.save_slot(2).unwrap();
+        assert!(save_state::slot_path(&save_dir, program.hash(), 2).exists());
+
+        chip.step(KeyInput::default(), period).unwrap();
+        assert_ne!(chip.get_state(), &saved_state);
+        chip.load_slot(2).unwrap();
+        assert_eq!(chip.get_state(), &saved_state);
+
+        assert!(matches!(
+            chip.load_slot(1),
+            Err(save_state::Error::Io(_, _))
+        ));
+        assert!(matches!(
+            chip.save_slot(5),
+            Err(save_state::Error::InvalidSlot(5))
+        ));
+
+        std::fs::remove_dir_all(save_dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_slot_without_program() {
+        let chip = EmulatedChip8::new();
+        assert!(matches!(
+            chip.save_slot(1),
+            Err(save_state::Error::NoProgramLoaded)
+        ));
+    }
 }
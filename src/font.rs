@@ -1,5 +1,22 @@
 use crate::emulator::Chip8State;
 
+/// Where [`Chip8Font::write`] puts the SUPER-CHIP large font, right after the small one
+pub const LARGE_FONT_START: u16 = 0x0A0;
+
+/// The SUPER-CHIP 8x10 font, which only has the decimal digits
+const LARGE_FONT: [u8; 100] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+];
+
 /// Represents a standard font on a Chip8 system
 pub struct Chip8Font {
     data: [u8; 80],
@@ -42,8 +59,20 @@ impl Chip8Font {
         ])
     }
 
+    /// Returns the 5-byte sprite for a hex digit
+    /// # Arguments
+    /// * `digit` - Digit to look up, from 0x0 to 0xF
+    pub fn glyph(&self, digit: u8) -> &[u8] {
+        let start = usize::from(digit & 0x0F) * 5;
+        &self.data[start..start + 5]
+    }
+
     pub fn write(&self, state: &mut Chip8State) {
         // By convention, we're putting the font in address range 0x050–0x09F
         state.memory[0x050..0x0A0].copy_from_slice(&self.data[..]);
+        // The large font isn't customisable, so it always goes in 0x0A0-0x103
+        let large_font_start = usize::from(LARGE_FONT_START);
+        state.memory[large_font_start..large_font_start + LARGE_FONT.len()]
+            .copy_from_slice(&LARGE_FONT);
     }
 }
@@ -0,0 +1,51 @@
+use std::hash::Hasher;
+
+/// 64-bit FNV-1a hasher. Unlike the std hashers, its output is guaranteed to stay the same across
+/// builds and platforms (as long as what's being hashed doesn't depend on the platform), so the
+/// hashes can be stored or compared between runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableHasher {
+    hash: u64,
+}
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+}
+
+impl Default for StableHasher {
+    fn default() -> StableHasher {
+        StableHasher {
+            hash: Self::OFFSET_BASIS,
+        }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StableHasher;
+    use std::hash::Hasher;
+    use test_case::test_case;
+
+    // Reference values from the FNV spec
+    #[test_case(b"", 0xcbf29ce484222325; "empty")]
+    #[test_case(b"a", 0xaf63dc4c8601ec8c; "a")]
+    #[test_case(b"foobar", 0x85944171f73967e8; "foobar")]
+    fn test_fnv1a(bytes: &[u8], expected: u64) {
+        let mut hasher = StableHasher::default();
+        hasher.write(bytes);
+        assert_eq!(hasher.finish(), expected);
+    }
+}
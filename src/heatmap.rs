@@ -0,0 +1,112 @@
+use std::fmt;
+
+const MEMORY_SIZE: usize = 4096;
+
+/// Counts how often each memory address gets read and written by the running program, to spot
+/// hot code, sprite tables and self-modifying code. Loading the font and program doesn't count.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoryHeatmap {
+    reads: Vec<u32>,
+    writes: Vec<u32>,
+}
+
+impl Default for MemoryHeatmap {
+    fn default() -> Self {
+        MemoryHeatmap {
+            reads: vec![0; MEMORY_SIZE],
+            writes: vec![0; MEMORY_SIZE],
+        }
+    }
+}
+
+impl MemoryHeatmap {
+    /// Records a read of `len` bytes starting at `start`
+    pub fn record_read(&mut self, start: usize, len: usize) {
+        increment(&mut self.reads, start, len);
+    }
+
+    /// Records a write of `len` bytes starting at `start`
+    pub fn record_write(&mut self, start: usize, len: usize) {
+        increment(&mut self.writes, start, len);
+    }
+
+    pub fn reads(&self, address: usize) -> u32 {
+        self.reads[address]
+    }
+
+    pub fn writes(&self, address: usize) -> u32 {
+        self.writes[address]
+    }
+
+    /// Returns the heatmap as CSV, with one `address,reads,writes` line for every address that
+    /// was accessed at all
+    pub fn to_csv(&self) -> Csv<'_> {
+        Csv(self)
+    }
+}
+
+fn increment(counts: &mut [u32], start: usize, len: usize) {
+    // Accesses running off the end of memory panic elsewhere, so we just clip them here
+    let end = (start + len).min(counts.len());
+    for count in counts.iter_mut().take(end).skip(start) {
+        *count = count.saturating_add(1);
+    }
+}
+
+/// CSV export of a [`MemoryHeatmap`], see [`MemoryHeatmap::to_csv`]
+pub struct Csv<'a>(&'a MemoryHeatmap);
+
+impl fmt::Display for Csv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "address,reads,writes")?;
+        for address in 0..MEMORY_SIZE {
+            let (reads, writes) = (self.0.reads(address), self.0.writes(address));
+            if reads > 0 || writes > 0 {
+                writeln!(f, "{address:#05x},{reads},{writes}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MemoryHeatmap;
+    use crate::{
+        emulator::{EmulatedChip8, KeyInput},
+        program::Program,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_heatmap_counts_accesses() {
+        // LD I, 0x300; LD [I], V1; LD V1, [I]; LD V0, [I]
+        let program =
+            Program::new_from_data(&[0xA3, 0x00, 0xF1, 0x55, 0xF1, 0x65, 0xF0, 0x65]).unwrap();
+        let mut chip = EmulatedChip8::new().with_heatmap(true);
+        chip.load_program(&program);
+        for _ in 0..4 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+
+        let heatmap = chip.heatmap().unwrap();
+        // Every instruction was fetched once
+        for address in 0x200..0x208 {
+            assert_eq!(heatmap.reads(address), 1, "address {address:#x}");
+        }
+        assert_eq!((heatmap.reads(0x300), heatmap.writes(0x300)), (2, 1));
+        assert_eq!((heatmap.reads(0x301), heatmap.writes(0x301)), (1, 1));
+        assert_eq!((heatmap.reads(0x302), heatmap.writes(0x302)), (0, 0));
+    }
+
+    #[test]
+    fn test_heatmap_csv() {
+        let mut heatmap = MemoryHeatmap::default();
+        heatmap.record_read(0x200, 2);
+        heatmap.record_write(0x201, 1);
+        assert_eq!(
+            heatmap.to_csv().to_string(),
+            "address,reads,writes\n0x200,1,0\n0x201,1,1\n"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::emulator::{EmulatedChip8, KeyInput, Result};
+use std::{fmt, time::Duration};
+
+/// A single synthetic key press, used to measure how long it takes the running program to notice
+/// a key. The key is held from `press_at` for `hold_for`, the same way the terminal renderer
+/// keeps a key pressed for a while after it sees it, since terminals don't report key releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyProbe {
+    pub key: u8,
+    /// Emulated time into the run at which the key gets pressed
+    pub press_at: Duration,
+    pub hold_for: Duration,
+}
+
+/// How long it took between a [`LatencyProbe`] pressing its key and a key check (`EX9E` or
+/// `EXA1`) seeing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLatency {
+    /// Instructions executed after the press, before the one that saw it
+    pub cycles: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for InputLatency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} cycles ({:.3}ms)",
+            self.cycles,
+            self.elapsed.as_secs_f64() * 1000.
+        )
+    }
+}
+
+/// Steps `chip` once every `period` with `probe`'s key scripted in, until a key check sees the key
+/// while it's pressed. Only `EX9E`/`EXA1` count, as they're how most programs poll for input.
+/// Returns `None` if that didn't happen within `max_steps`, e.g. because the key was released
+/// before the program got round to checking it.
+pub fn measure_input_latency(
+    chip: &mut EmulatedChip8,
+    probe: LatencyProbe,
+    period: Duration,
+    max_steps: usize,
+) -> Result<Option<InputLatency>> {
+    let mut key_input = KeyInput::default();
+    let mut now = Duration::ZERO;
+    let mut press_step = None;
+    for step in 0..max_steps {
+        let pressed = now >= probe.press_at && now < probe.press_at + probe.hold_for;
+        if pressed && press_step.is_none() {
+            press_step = Some(step);
+        }
+        key_input.key_state[usize::from(probe.key & 0x0F)] = pressed;
+
+        if let Some(press_step) = press_step {
+            if pressed && checks_key(chip, probe.key) {
+                let cycles = step - press_step;
+                return Ok(Some(InputLatency {
+                    cycles,
+                    elapsed: period * cycles as u32,
+                }));
+            }
+        }
+
+        chip.step(key_input.clone(), period)?;
+        now += period;
+    }
+    Ok(None)
+}
+
+/// Whether the next instruction `chip` will run is a key check against `key`
+fn checks_key(chip: &EmulatedChip8, key: u8) -> bool {
+    let state = chip.get_state();
+    let pc = usize::from(state.pc.0);
+    let Ok(opcode) = state.memory_slice(pc..pc + 2) else {
+        return false;
+    };
+    let opcode = u16::from_be_bytes([opcode[0], opcode[1]]);
+    let is_key_check = matches!(opcode & 0xF0FF, 0xE09E | 0xE0A1);
+    let x = usize::from((opcode >> 8) & 0x0F);
+    is_key_check && state.gp_registers[x].0 & 0x0F == key & 0x0F
+}
+
+#[cfg(test)]
+mod test {
+    use super::{measure_input_latency, InputLatency, LatencyProbe};
+    use crate::{emulator::EmulatedChip8, program::Program};
+    use std::time::Duration;
+    use test_case::test_case;
+
+    const PERIOD: Duration = Duration::from_millis(1);
+
+    /// Waits for key 5 with a padded polling loop that only checks the key every 4 instructions:
+    /// LD V0, 5; SKP V0; ADD V1, 1; ADD V1, 1; JP 0x202
+    fn polling_program() -> Program {
+        Program::new_from_data(&[0x60, 0x05, 0xE0, 0x9E, 0x71, 0x01, 0x71, 0x01, 0x12, 0x02])
+            .unwrap()
+    }
+
+    // SKP runs on steps 1, 5, 9, 13, ...
+    #[test_case(10, 500, Some((3, 3)); "waits_for_next_check")]
+    #[test_case(13, 500, Some((0, 0)); "checked_straight_away")]
+    #[test_case(10, 2, None; "released_before_check")]
+    fn test_input_latency(press_at_ms: u64, hold_for_ms: u64, expected: Option<(usize, u64)>) {
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&polling_program());
+        let probe = LatencyProbe {
+            key: 0x5,
+            press_at: Duration::from_millis(press_at_ms),
+            hold_for: Duration::from_millis(hold_for_ms),
+        };
+        let latency = measure_input_latency(&mut chip, probe, PERIOD, 100).unwrap();
+        assert_eq!(
+            latency,
+            expected.map(|(cycles, elapsed_ms)| InputLatency {
+                cycles,
+                elapsed: Duration::from_millis(elapsed_ms),
+            })
+        );
+    }
+}
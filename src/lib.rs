@@ -0,0 +1,29 @@
+//! A CHIP-8 emulator. [`emulator::EmulatedChip8`] is the place to start: load a
+//! [`program::Program`] into it and step it, or hand it a [`renderer::Renderer`] to run. The
+//! `chip8_test` binary is a terminal frontend built on top of this.
+
+pub mod audio;
+pub mod batch;
+pub mod boot;
+pub mod config;
+pub mod coverage;
+pub mod diagnostics;
+pub mod disasm;
+pub mod display;
+pub mod emulator;
+pub mod font;
+pub mod hash;
+pub mod heatmap;
+pub mod latency;
+pub mod opcodes;
+pub mod program;
+pub mod quirks;
+pub mod renderer;
+pub mod report;
+pub mod rng;
+pub mod roms;
+pub mod save_state;
+pub mod stack;
+pub mod timing;
+pub mod trace;
+pub mod validate;
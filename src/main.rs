@@ -1,21 +1,25 @@
-mod display;
-mod emulator;
-mod font;
-mod opcodes;
-mod program;
-mod renderer;
-
-use crate::{
-    emulator::EmulatedChip8,
+use anyhow::Context;
+use chip8_test::{
+    boot::BootSplash,
+    config::{Config, DEFAULT_SPEED},
+    diagnostics::Diagnostics,
+    disasm,
+    emulator::{Address, EmulatedChip8},
     font::Chip8Font,
-    program::Program,
-    renderer::{Renderer, TuiRenderer},
+    latency::{self, LatencyProbe},
+    program::{Program, PROGRAM_START},
+    quirks::{MemoryIncrementQuirk, QuirkConfig, ReservedExecution, ShiftFlagOrder, ShiftQuirk},
+    renderer::{Aspect, GlyphSet, KeyMap, PalettePreset, Renderer, TuiOptions, TuiRenderer},
+    report::RunReport,
+    roms::BUNDLED_ROMS,
+    timing::FRAME_PERIOD,
+    trace, validate,
 };
 use clap::Parser;
-use log::{debug, error, info, LevelFilter};
+use log::{error, warn, LevelFilter};
 use log4rs::{
     append::file::FileAppender,
-    config::{Appender, Config, Root},
+    config::{Appender, Root},
     encode::pattern::PatternEncoder,
 };
 use spin_sleep::LoopHelper;
@@ -23,21 +27,43 @@ use std::{
     any::Any,
     backtrace::Backtrace,
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 /// A chip 8 emulator, running with a GUI
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to the program to load
+    /// Path to the program to load. Use `@name` to load one of the bundled ROMs instead
+    #[arg(short, long, required_unless_present = "list_roms")]
+    program: Option<PathBuf>,
+
+    /// Lists the ROMs bundled with the emulator and exits
+    #[arg(long)]
+    list_roms: bool,
+
+    /// Statically checks the program for common problems and exits without running it. Exits
+    /// with a nonzero status if any errors were found
+    #[arg(long)]
+    validate: bool,
+
+    /// Prints the program disassembled into CHIP-8 assembly and exits without running it
+    #[arg(long)]
+    disasm: bool,
+
+    /// TOML file to read options from. Any options passed on the command line take precedence
     #[arg(short, long)]
-    program: PathBuf,
+    config: Option<PathBuf>,
 
     /// The speed at which the processor runs, in Hz.
     /// Default is 700 instructions/second as a rough average of real timing
-    #[arg(short, long, default_value_t = 700.)]
-    speed: f64,
+    #[arg(short, long)]
+    speed: Option<f64>,
+
+    /// How long (in ms) to ease into a new speed when it changes, rather than switching
+    /// instantly. Defaults to 0, which disables ramping
+    #[arg(long)]
+    speed_ramp_ms: Option<u64>,
 
     /// The path to log output to
     #[arg(short, long)]
@@ -46,14 +72,239 @@ struct Args {
     /// Enables verbose logging (logs debug logs too)
     #[arg(short, long)]
     verbose: bool,
+
+    /// Log and skip over unsupported opcodes instead of stopping the emulator
+    #[arg(long)]
+    skip_unknown_opcodes: bool,
+
+    /// Directory quick save slots (F1-F4 to save, Shift+F1-F4 to load) are stored in. Defaults
+    /// to the current directory
+    #[arg(long)]
+    save_dir: Option<PathBuf>,
+
+    /// Log a warning when a sprite made up entirely of zeros is drawn, which usually means the
+    /// ROM is reading sprites from the wrong place
+    #[arg(long)]
+    warn_on_blank_sprite: bool,
+
+    /// Log a warning if the emulator exits with return addresses still on the stack, which
+    /// usually means calls and returns don't pair up
+    #[arg(long)]
+    warn_on_stack_imbalance: bool,
+
+    /// Draw the display with plain ASCII characters instead of Unicode half blocks. This is
+    /// picked automatically when the terminal doesn't look like it supports Unicode
+    #[arg(long)]
+    ascii: bool,
+
+    /// Characters to draw the display with: `halfblock` packs two rows of pixels into each line,
+    /// while `ascii` and `fullblock` take a line per row. Picked from the terminal when not set
+    #[arg(long, value_enum, conflicts_with = "ascii")]
+    glyphs: Option<GlyphSet>,
+
+    /// How wide to draw each pixel with ASCII characters. `correct` draws two characters per
+    /// pixel, since terminal cells are about twice as tall as they are wide. Defaults to `square`
+    #[arg(long, value_enum)]
+    aspect: Option<Aspect>,
+
+    /// Colours to draw the display in. Defaults to `white`
+    #[arg(long, value_enum)]
+    palette: Option<PalettePreset>,
+
+    /// Fade pixels out over a few frames instead of turning them off straight away, which hides
+    /// most of the flicker from sprites being erased and redrawn
+    #[arg(long)]
+    ghosting: bool,
+
+    /// Shows the registers, timers and stack depth in a panel next to the display
+    #[arg(long)]
+    debug_panel: bool,
+
+    /// Records the display to an animated GIF at this path. Recording stops with Ctrl+R, or when
+    /// the emulator exits
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Pitch of the buzzer in Hz, when built with the `audio` feature. Defaults to 440
+    #[arg(long)]
+    buzzer_frequency: Option<f32>,
+
+    /// TOML file mapping keyboard keys to keypad buttons, e.g. `a = 0x4`. Replaces the default
+    /// mapping, which uses the 4x4 block of keys from `1` to `v`
+    #[arg(long)]
+    keymap: Option<PathBuf>,
+
+    /// Whether shifts (8XY6/8XYE) shift VX in place, or copy VY into VX first like the COSMAC VIP.
+    /// Defaults to in-place
+    #[arg(long, value_enum)]
+    shift_quirk: Option<ShiftQuirk>,
+
+    /// Which write wins when a shift (8XY6/8XYE) targets VF. Defaults to flag-last, where VF
+    /// ends up holding the shifted-out bit
+    #[arg(long, value_enum)]
+    shift_flag_order: Option<ShiftFlagOrder>,
+
+    /// Records which parts of the ROM and which opcodes get executed, and prints a report once
+    /// the emulator exits
+    #[arg(long)]
+    coverage: bool,
+
+    /// Writes a listing of the ROM to this file once the emulator exits, showing how many times
+    /// each instruction got executed. Useful for finding hot loops and dead code
+    #[arg(long)]
+    profile_listing: Option<PathBuf>,
+
+    /// Counts how often the program reads and writes each memory address, and writes the counts
+    /// to this file as CSV once the emulator exits
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+
+    /// Writes a report to this file once the emulator exits, with the final registers, stack,
+    /// timers and display, instruction counts, and any warnings. Handy to attach to bug reports
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Writes a one line summary of the machine state (PC, I, some registers, timers and lit
+    /// pixel count) to this file on every frame
+    #[arg(long)]
+    frame_log: Option<PathBuf>,
+
+    /// Starts execution at this address (in hex) instead of the program's entry point, e.g. to
+    /// jump straight into a subroutine
+    #[arg(long, value_parser = parse_address)]
+    entry: Option<u16>,
+
+    /// Instead of opening the emulator, runs the program headless and compares it instruction by
+    /// instruction against this reference trace (e.g. from another emulator), reporting the first
+    /// place they differ. See `TraceEntry` for the format
+    #[arg(long)]
+    diff_trace: Option<PathBuf>,
+
+    /// Instead of opening the emulator, runs the program headless, presses this key (0-F) a second
+    /// in, and prints how long it takes until the program checks it (with EX9E/EXA1). Useful for
+    /// tuning input handling
+    #[arg(long, value_parser = parse_key)]
+    input_latency: Option<u8>,
+
+    /// Whether FX55/FX65 move I past the registers they store or load, like the COSMAC VIP did.
+    /// Defaults to leave, where I is unchanged
+    #[arg(long, value_enum)]
+    memory_increment: Option<MemoryIncrementQuirk>,
+
+    /// Stop with an error when a key check (EX9E/EXA1) is given a key above 0xF, rather than
+    /// only using its low nibble. Useful for catching ROM bugs
+    #[arg(long)]
+    strict_keys: bool,
+
+    /// Have the clear screen instruction (00E0) also reset VF to 0, like some interpreters did
+    #[arg(long)]
+    cls_resets_vf: bool,
+
+    /// Wrap memory accesses through I (e.g. by FX55/FX65) that run past the end of memory back
+    /// round to the start, instead of stopping with an error
+    #[arg(long)]
+    index_wraps: bool,
+
+    /// Set VF on DXYN when rows of the sprite get clipped off the bottom of the screen, like
+    /// SUPER-CHIP does
+    #[arg(long)]
+    clipped_rows_collide: bool,
+
+    /// Have DXYN draw nothing when it starts off the screen, instead of wrapping the start
+    /// position back round onto it
+    #[arg(long)]
+    drw_start_clips: bool,
+
+    /// What to do when the program runs code below 0x200, in the font or where the interpreter
+    /// used to live. Defaults to warn, as it's almost always a runaway jump
+    #[arg(long, value_enum)]
+    reserved_execution: Option<ReservedExecution>,
+
+    /// Pause the CPU after each draw (DXYN) until the next frame, so at most one draw happens per
+    /// frame. Fixes flicker in games that expect this
+    #[arg(long)]
+    one_draw_per_frame: bool,
+
+    /// Shows a splash screen for this long (in ms) before the program starts, like some original
+    /// interpreters did. Press any key to skip it. Defaults to 0, which disables the splash
+    #[arg(long)]
+    boot_delay_ms: Option<u64>,
+}
+
+impl Args {
+    /// Options set on the command line, as a [`Config`] that can be layered over a config file
+    fn as_config(&self) -> Config {
+        Config {
+            speed: self.speed,
+            speed_ramp_ms: self.speed_ramp_ms,
+            save_dir: self.save_dir.clone(),
+            // Flags can only turn things on, so leave them unset when not passed
+            skip_unknown_opcodes: self.skip_unknown_opcodes.then_some(true),
+            warn_on_blank_sprite: self.warn_on_blank_sprite.then_some(true),
+            warn_on_stack_imbalance: self.warn_on_stack_imbalance.then_some(true),
+            ascii: self.ascii.then_some(true),
+            glyphs: self.glyphs,
+            aspect: self.aspect,
+            palette: self.palette,
+            ghosting: self.ghosting.then_some(true),
+            debug_panel: self.debug_panel.then_some(true),
+            buzzer_frequency: self.buzzer_frequency,
+            keymap: self.keymap.clone(),
+            shift_quirk: self.shift_quirk,
+            shift_flag_order: self.shift_flag_order,
+            memory_increment: self.memory_increment,
+            strict_keys: self.strict_keys.then_some(true),
+            cls_resets_vf: self.cls_resets_vf.then_some(true),
+            index_wraps: self.index_wraps.then_some(true),
+            clipped_rows_collide: self.clipped_rows_collide.then_some(true),
+            drw_start_clips: self.drw_start_clips.then_some(true),
+            reserved_execution: self.reserved_execution,
+            one_draw_per_frame: self.one_draw_per_frame.then_some(true),
+            boot_delay_ms: self.boot_delay_ms,
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    if args.list_roms {
+        for rom in BUNDLED_ROMS {
+            println!("@{:<12} {}", rom.name, rom.description);
+        }
+        return Ok(());
+    }
+    let config = match &args.config {
+        Some(config_path) => args.as_config().or(Config::new_from_file(config_path)?),
+        None => args.as_config(),
+    };
+    let speed = config.speed.unwrap_or(DEFAULT_SPEED);
+
+    // Clap makes sure we have a program unless we're listing ROMs
+    let program = Program::new_from_source(args.program.expect("no program provided"))?;
+    if args.validate {
+        let report = validate::validate(&program);
+        print!("{report}");
+        if report.has_errors() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.disasm {
+        for (address, opcode, mnemonic) in disasm::disassemble(program.data(), PROGRAM_START) {
+            println!("{}", disasm::format_line(address, opcode, &mnemonic));
+        }
+        return Ok(());
+    }
+
     if let Some(log_path) = args.log_path {
         setup_logging(log_path, args.verbose)?;
     }
 
+    // Running a ROM with a broken entry point fails straight away, so give a hint as to why
+    if let Some(issue) = validate::check_entry(&program) {
+        warn!("{issue}");
+    }
+
     std::panic::set_hook(Box::new(move |panic_info| {
         let backtrace = Backtrace::capture();
         let payload = payload_as_str(panic_info.payload());
@@ -70,39 +321,144 @@ fn main() -> anyhow::Result<()> {
         std::process::exit(1);
     }));
 
-    let period_draw = Duration::from_secs_f64(1. / 60.);
-    let mut renderer = TuiRenderer::new(period_draw)?;
-
-    let mut emulated_chip8 = EmulatedChip8::new();
+    let mut emulated_chip8 = EmulatedChip8::new()
+        .with_skip_unknown_opcodes(config.skip_unknown_opcodes.unwrap_or(false))
+        .with_save_dir(config.save_dir.unwrap_or_else(|| PathBuf::from(".")))
+        .with_coverage(args.coverage || args.profile_listing.is_some() || args.report.is_some())
+        .with_heatmap(args.heatmap.is_some())
+        .with_one_draw_per_frame(config.one_draw_per_frame.unwrap_or(false))
+        .with_speed_ramp(Duration::from_millis(config.speed_ramp_ms.unwrap_or(0)))
+        .with_quirks(QuirkConfig {
+            shift_quirk: config.shift_quirk.unwrap_or_default(),
+            shift_flag_order: config.shift_flag_order.unwrap_or_default(),
+            memory_increment: config.memory_increment.unwrap_or_default(),
+            strict_key_values: config.strict_keys.unwrap_or(false),
+            cls_resets_vf: config.cls_resets_vf.unwrap_or(false),
+            index_wraps: config.index_wraps.unwrap_or(false),
+            clipped_rows_collide: config.clipped_rows_collide.unwrap_or(false),
+            drw_start_wraps: !config.drw_start_clips.unwrap_or(false),
+            reserved_execution: config.reserved_execution.unwrap_or_default(),
+        })
+        .with_diagnostics(Diagnostics {
+            warn_on_blank_sprite: config.warn_on_blank_sprite.unwrap_or(false),
+            warn_on_stack_imbalance: config.warn_on_stack_imbalance.unwrap_or(false),
+            ..Diagnostics::default()
+        });
+    if let Some(frame_log) = &args.frame_log {
+        let file = std::fs::File::create(frame_log)
+            .with_context(|| format!("failed to create frame log {}", frame_log.display()))?;
+        emulated_chip8 = emulated_chip8.with_frame_log(Box::new(std::io::BufWriter::new(file)));
+    }
     // Load up font and program
-    emulated_chip8.write_font(&Chip8Font::new_from_default()?);
-    emulated_chip8.load_program(&Program::new_from_file(args.program)?);
+    let font = Chip8Font::new_from_default()?;
+    emulated_chip8.write_font(&font);
+    emulated_chip8.load_program(&program);
+    if let Some(entry) = args.entry {
+        if !entry.is_multiple_of(2) {
+            warn!("entry point {entry:#06x} is odd, so may land between instructions");
+        }
+        emulated_chip8.set_pc(Address(entry))?;
+    }
 
-    let mut last_draw = Instant::now();
-    let mut lh = LoopHelper::builder().build_with_target_rate(args.speed);
-    let expected_period = Duration::from_secs_f64(1. / args.speed);
+    if let Some(trace_path) = &args.diff_trace {
+        let reference = std::fs::read_to_string(trace_path)
+            .with_context(|| format!("failed to read trace {}", trace_path.display()))?;
+        let reference = trace::parse_trace(&reference)?;
+        let period = Duration::from_secs_f64(1. / speed);
+        match trace::diff_trace(&mut emulated_chip8, &reference, period)? {
+            Some(divergence) => {
+                print!("{divergence}");
+                std::process::exit(1);
+            }
+            None => println!("matched all {} instructions of the trace", reference.len()),
+        }
+        return Ok(());
+    }
 
-    loop {
-        lh.loop_start();
+    if let Some(key) = args.input_latency {
+        let probe = LatencyProbe {
+            key,
+            press_at: Duration::from_secs(1),
+            hold_for: TuiRenderer::KEY_PRESS_DURATION,
+        };
+        let period = Duration::from_secs_f64(1. / speed);
+        // Give up once the key has long been released
+        let max_steps = (10. * speed) as usize;
+        match latency::measure_input_latency(&mut emulated_chip8, probe, period, max_steps)? {
+            Some(latency) => println!("key {key:#x} was seen after {latency}"),
+            None => println!("key {key:#x} was never checked while it was pressed"),
+        }
+        return Ok(());
+    }
 
-        // Check if screen is still alive
-        if renderer.terminated() {
-            info!("terminating program");
-            debug!("final state:\n{}", emulated_chip8.get_state());
-            break;
+    let mut renderer = TuiRenderer::new_with_options(
+        FRAME_PERIOD,
+        TuiOptions {
+            glyphs: config
+                .glyphs
+                .unwrap_or_else(|| GlyphSet::detect(config.ascii.unwrap_or(false))),
+            aspect: config.aspect.unwrap_or_default(),
+            palette: config.palette.unwrap_or_default().into(),
+            ghosting: config.ghosting.unwrap_or(false),
+            debug_panel: config.debug_panel.unwrap_or(false),
+            record: args.record.clone(),
+            buzzer_frequency: config.buzzer_frequency,
+            keymap: match &config.keymap {
+                Some(keymap_path) => KeyMap::new_from_file(keymap_path)?,
+                None => KeyMap::default(),
+            },
+        },
+    )?;
+
+    let boot_delay = Duration::from_millis(config.boot_delay_ms.unwrap_or(0));
+    if !boot_delay.is_zero() {
+        show_boot_splash(
+            &mut renderer,
+            BootSplash::new(boot_delay, &font),
+            FRAME_PERIOD,
+        )?;
+    }
+
+    emulated_chip8.run(renderer, speed)?;
+
+    if let Some(coverage) = emulated_chip8.coverage() {
+        if args.coverage {
+            print!("{}", coverage.report(&program));
         }
+        if let Some(path) = &args.profile_listing {
+            std::fs::write(path, coverage.listing(&program).to_string()).with_context(|| {
+                format!("failed to write profile listing to {}", path.display())
+            })?;
+        }
+    }
+    if let (Some(path), Some(heatmap)) = (&args.heatmap, emulated_chip8.heatmap()) {
+        std::fs::write(path, heatmap.to_csv().to_string())
+            .with_context(|| format!("failed to write heatmap to {}", path.display()))?;
+    }
+    if let Some(path) = &args.report {
+        std::fs::write(path, RunReport::new(&emulated_chip8).to_string())
+            .with_context(|| format!("failed to write report to {}", path.display()))?;
+    }
 
-        // Fetch key state
-        let key_input = renderer.current_key_state();
+    Ok(())
+}
 
-        emulated_chip8.step(key_input, expected_period)?;
-        if last_draw.elapsed() > period_draw {
-            last_draw = Instant::now();
-            renderer.update_screen(&emulated_chip8.get_state().display)?;
+/// Shows the boot splash until it runs out or gets skipped. Returns early if the renderer
+/// terminates, leaving [`EmulatedChip8::run`] to notice.
+fn show_boot_splash<R: Renderer>(
+    renderer: &mut R,
+    mut splash: BootSplash,
+    period_draw: Duration,
+) -> anyhow::Result<()> {
+    let mut lh = LoopHelper::builder().build_with_target_rate(1. / period_draw.as_secs_f64());
+    renderer.update_screen(splash.display())?;
+    while !renderer.terminated() {
+        let elapsed = lh.loop_start();
+        if splash.advance(elapsed, &renderer.current_key_state()) {
+            break;
         }
         lh.loop_sleep();
     }
-
     Ok(())
 }
 
@@ -121,7 +477,7 @@ fn setup_logging<P: AsRef<Path>>(file: P, verbose: bool) -> anyhow::Result<()> {
         .encoder(Box::new(PatternEncoder::new("{d} - {m}{n}")))
         .build(file)?;
 
-    let config = Config::builder()
+    let config = log4rs::Config::builder()
         .appender(Appender::builder().build("file", Box::new(file_appender)))
         .build(Root::builder().appender("file").build(if verbose {
             LevelFilter::Debug
@@ -133,3 +489,22 @@ fn setup_logging<P: AsRef<Path>>(file: P, verbose: bool) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Parses a keypad key, as a single hex digit
+fn parse_key(key: &str) -> Result<u8, String> {
+    match u8::from_str_radix(key.trim_start_matches("0x"), 16) {
+        Ok(key) if key <= 0xF => Ok(key),
+        _ => Err(format!("{key} is not a key, keys go from 0 to F")),
+    }
+}
+
+/// Parses a memory address, in hex
+fn parse_address(address: &str) -> Result<u16, String> {
+    match u16::from_str_radix(address.trim_start_matches("0x"), 16) {
+        Ok(value) if value <= 0xFFF => Ok(value),
+        Ok(_) => Err(format!(
+            "{address} is outside of memory, addresses go from 0 to FFF"
+        )),
+        Err(_) => Err(format!("{address} is not a hex address")),
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{
-    display::Coordinates,
-    emulator::{Address, Chip8State, Register},
+    diagnostics::Warning,
+    display::{Coordinates, DrawMode, HIGH_RES, SCREEN_RES},
+    emulator::{Address, Chip8State, Fault, Register, Result},
+    font::LARGE_FONT_START,
+    quirks::{MemoryIncrementQuirk, ShiftFlagOrder, ShiftQuirk},
 };
 use byteorder::{BigEndian, ByteOrder};
+use std::sync::OnceLock;
 
 /// Data extracted from the 16-bit opcode. Uniform across all opcodes (though not used by all).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -40,7 +44,7 @@ impl OpCodeData {
 }
 
 /// Implemented by any struct that can read a specific Chip8 opcode
-pub trait OpCodeReader: std::fmt::Debug {
+pub trait OpCodeReader: std::fmt::Debug + Send + Sync {
     /// This is the value identifying the opcode. It'll be matched against the mask bellow so be
     /// sure to set any valiable bits to 0
     fn opcode_val(&self) -> u16;
@@ -48,9 +52,133 @@ pub trait OpCodeReader: std::fmt::Debug {
     /// Mask of the opcode prefix
     fn opcode_mask(&self) -> u16;
 
+    /// The instruction in assembly form, e.g. `LD V0, 0x12`, for disassembly
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String;
+
     /// Use this to actually process a chip 8 opcode from a given CPU state and decoded
-    /// instruction. Note we will have incremented PC  by 2 bytes by the time this is called
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData);
+    /// instruction. Note we will have incremented PC  by 2 bytes by the time this is called.
+    /// Fails when the instruction runs into something the program can't carry on from, usually
+    /// with a [`Fault`]
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result;
+}
+
+/// Returns one reader for every opcode we support
+pub fn supported_instructions() -> Vec<Box<dyn OpCodeReader>> {
+    vec![
+        Box::new(ClearScreen),
+        Box::new(Jump),
+        Box::new(SetRegisterConst),
+        Box::new(AddRegisterConst),
+        Box::new(SetIndexRegister),
+        Box::new(DisplayDraw),
+        Box::new(SubroutineCall),
+        Box::new(SubroutineReturn),
+        Box::new(SkipConstEqual),
+        Box::new(SkipConstNotEqual),
+        Box::new(SkipRegistersEqual),
+        Box::new(SkipRegistersNotEqual),
+        Box::new(SetRegisterRegister),
+        Box::new(BinaryOr),
+        Box::new(BinaryAnd),
+        Box::new(BinaryXor),
+        Box::new(AddRegisters),
+        Box::new(SubtractRegisters),
+        Box::new(SubtractRegistersReverse),
+        Box::new(ShiftRegisterRight),
+        Box::new(ShiftRegisterLeft),
+        Box::new(JumpOffset),
+        Box::new(Random),
+        Box::new(SkipIfKey),
+        Box::new(SkipIfNotKey),
+        Box::new(ReadDelayTimer),
+        Box::new(SetDelayTimer),
+        Box::new(SetSoundTimer),
+        Box::new(AddIndexRegister),
+        Box::new(GetKey),
+        Box::new(ReadFontCharacter),
+        Box::new(ReadLargeFontCharacter),
+        Box::new(DecimalDecoding),
+        Box::new(StoreMemory),
+        Box::new(LoadMemory),
+        Box::new(StoreRegisterRange),
+        Box::new(LoadRegisterRange),
+        Box::new(ScrollDown),
+        Box::new(ScrollRight),
+        Box::new(ScrollLeft),
+        Box::new(LowRes),
+        Box::new(HighRes),
+    ]
+}
+
+/// Returns true if any of `instructions` can handle `opcode`
+pub fn is_supported(instructions: &[Box<dyn OpCodeReader>], opcode: u16) -> bool {
+    linear_lookup(instructions, opcode).is_some()
+}
+
+/// Finds the first reader matching `opcode` by checking every instruction's mask in turn. This
+/// is the reference behaviour [`DecodeTable`] has to reproduce.
+pub fn linear_lookup(
+    instructions: &[Box<dyn OpCodeReader>],
+    opcode: u16,
+) -> Option<&dyn OpCodeReader> {
+    instructions
+        .iter()
+        .find(|instruction| opcode & instruction.opcode_mask() == instruction.opcode_val())
+        .map(|instruction| instruction.as_ref())
+}
+
+/// Resolves opcodes to their readers without scanning the whole instruction list. Instructions
+/// are bucketed by the top nibble they can match, so a lookup only has to check the handful of
+/// instructions sharing that nibble.
+#[derive(Debug)]
+pub struct DecodeTable {
+    instructions: Vec<Box<dyn OpCodeReader>>,
+    /// Indices into `instructions` for every top nibble, kept in the original order so the first
+    /// match wins just like in [`linear_lookup`]
+    buckets: [Vec<usize>; 0x10],
+}
+
+impl DecodeTable {
+    pub fn new(instructions: Vec<Box<dyn OpCodeReader>>) -> DecodeTable {
+        let mut buckets: [Vec<usize>; 0x10] = Default::default();
+        for (nibble, bucket) in buckets.iter_mut().enumerate() {
+            let prefix = (nibble as u16) << 12;
+            for (idx, instruction) in instructions.iter().enumerate() {
+                // Only the top nibble of the mask matters here, the rest gets checked on lookup
+                let mask = instruction.opcode_mask() & 0xF000;
+                if prefix & mask == instruction.opcode_val() & mask {
+                    bucket.push(idx);
+                }
+            }
+        }
+
+        DecodeTable {
+            instructions,
+            buckets,
+        }
+    }
+
+    pub fn lookup(&self, opcode: u16) -> Option<&dyn OpCodeReader> {
+        self.buckets[(opcode >> 12) as usize]
+            .iter()
+            .map(|idx| self.instructions[*idx].as_ref())
+            .find(|instruction| opcode & instruction.opcode_mask() == instruction.opcode_val())
+    }
+}
+
+impl DecodeTable {
+    /// Returns a table of all [`supported_instructions`], built the first time this is called and
+    /// shared by every emulator after that
+    pub fn shared() -> &'static DecodeTable {
+        static SHARED: OnceLock<DecodeTable> = OnceLock::new();
+        SHARED.get_or_init(DecodeTable::default)
+    }
+}
+
+impl Default for DecodeTable {
+    fn default() -> Self {
+        Self::new(supported_instructions())
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -65,8 +193,16 @@ impl OpCodeReader for ClearScreen {
         0xffff
     }
 
-    fn execute(&self, state: &mut Chip8State, _: OpCodeData) {
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "CLS".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _: OpCodeData) -> Result {
         state.display.clear();
+        if state.quirks.cls_resets_vf {
+            state.gp_register(0xF).0 = 0;
+        }
+        Ok(())
     }
 }
 
@@ -82,8 +218,13 @@ impl OpCodeReader for Jump {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("JP 0x{:03X}", opcode_data.nnn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.pc = Address(opcode_data.nnn);
+        Ok(())
     }
 }
 
@@ -99,8 +240,13 @@ impl OpCodeReader for SetRegisterConst {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD V{:X}, 0x{:02X}", opcode_data.x, opcode_data.nn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         *state.gp_register(opcode_data.x) = Register(opcode_data.nn);
+        Ok(())
     }
 }
 
@@ -116,8 +262,13 @@ impl OpCodeReader for AddRegisterConst {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("ADD V{:X}, 0x{:02X}", opcode_data.x, opcode_data.nn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         *state.gp_register(opcode_data.x) += opcode_data.nn;
+        Ok(())
     }
 }
 
@@ -133,8 +284,13 @@ impl OpCodeReader for SetIndexRegister {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD I, 0x{:03X}", opcode_data.nnn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.index_register = Address(opcode_data.nnn);
+        Ok(())
     }
 }
 
@@ -150,16 +306,62 @@ impl OpCodeReader for DisplayDraw {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let draw_coordinates = Coordinates::new(
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!(
+            "DRW V{:X}, V{:X}, {}",
+            opcode_data.x, opcode_data.y, opcode_data.n
+        )
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        // In high resolution, DXY0 draws a 16x16 sprite, two bytes per row
+        let wide = opcode_data.n == 0 && state.display.resolution() == HIGH_RES;
+        let (rows, bytes_per_row) = if wide {
+            (16, 2)
+        } else {
+            (usize::from(opcode_data.n), 1)
+        };
+        let Some(draw_coordinates) = Coordinates::from_registers(
             state.gp_register(opcode_data.x).0,
             state.gp_register(opcode_data.y).0,
-        );
-        let rows: usize = opcode_data.n.into();
-        let sprite_start: usize = state.index_register.into();
-        let sprite_end = sprite_start + rows;
-        let sprite = &state.memory[sprite_start..sprite_end];
-        state.display.apply_sprite(sprite, draw_coordinates);
+            state.display.resolution(),
+            state.quirks.drw_start_wraps,
+        ) else {
+            // The sprite starts off the screen, so all of its rows get clipped
+            let collided = rows > 0 && state.quirks.clipped_rows_collide;
+            state.gp_register(0xF).0 = u8::from(collided);
+            return Ok(());
+        };
+        // Sprites are at most 16 rows of 2 bytes
+        let mut sprite = [0; 32];
+        for (offset, byte) in sprite[..rows * bytes_per_row].iter_mut().enumerate() {
+            *byte = state.mem_at_index(offset as u16)?;
+        }
+        let sprite = &sprite[..rows * bytes_per_row];
+        let blank_sprite = rows > 0 && sprite.iter().all(|byte| *byte == 0);
+        let collisions = if wide {
+            state
+                .display
+                .apply_wide_sprite_collisions(sprite, draw_coordinates, DrawMode::Xor)
+        } else {
+            state
+                .display
+                .apply_sprite_collisions(sprite, draw_coordinates, DrawMode::Xor)
+        };
+        let clipped = usize::from(draw_coordinates.y) + rows > state.display.resolution().height;
+        let collided = !collisions.is_empty() || (clipped && state.quirks.clipped_rows_collide);
+        state.gp_register(0xF).0 = u8::from(collided);
+        if state.diagnostics.report_collisions {
+            state.last_collisions = collisions;
+        }
+
+        if blank_sprite && state.diagnostics.warn_on_blank_sprite {
+            state.warn(Warning::BlankSprite {
+                index: state.index_register,
+                rows: rows as u8,
+            });
+        }
+        Ok(())
     }
 }
 
@@ -175,9 +377,19 @@ impl OpCodeReader for SubroutineCall {
         0xf000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        state.stack.push_back(state.pc);
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("CALL 0x{:03X}", opcode_data.nnn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        // PC has already moved past the call
+        let call_address = Address(state.pc.0.wrapping_sub(2));
+        state
+            .stack
+            .push(state.pc)
+            .map_err(|_| Fault::StackOverflow(call_address))?;
         state.pc = Address(opcode_data.nnn);
+        Ok(())
     }
 }
 
@@ -193,9 +405,18 @@ impl OpCodeReader for SubroutineReturn {
         0xFFFF
     }
 
-    fn execute(&self, state: &mut Chip8State, _opcode_data: OpCodeData) {
-        let return_address = state.stack.pop_back().expect("no elements to pop");
-        state.pc = return_address;
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "RET".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _opcode_data: OpCodeData) -> Result {
+        // PC has already moved past the return
+        let return_at = Address(state.pc.0.wrapping_sub(2));
+        state.pc = state
+            .stack
+            .pop()
+            .map_err(|_| Fault::StackUnderflow(return_at))?;
+        Ok(())
     }
 }
 
@@ -211,10 +432,15 @@ impl OpCodeReader for SkipConstEqual {
         0xF000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SE V{:X}, 0x{:02X}", opcode_data.x, opcode_data.nn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         if state.gp_register(opcode_data.x).0 == opcode_data.nn {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
@@ -230,10 +456,15 @@ impl OpCodeReader for SkipConstNotEqual {
         0xF000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SNE V{:X}, 0x{:02X}", opcode_data.x, opcode_data.nn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         if state.gp_register(opcode_data.x).0 != opcode_data.nn {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
@@ -249,10 +480,15 @@ impl OpCodeReader for SkipRegistersEqual {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SE V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         if state.gp_register(opcode_data.x).0 == state.gp_register(opcode_data.y).0 {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
@@ -268,10 +504,15 @@ impl OpCodeReader for SkipRegistersNotEqual {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SNE V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         if state.gp_register(opcode_data.x).0 != state.gp_register(opcode_data.y).0 {
             state.pc += 2;
         }
+        Ok(())
     }
 }
 
@@ -287,8 +528,13 @@ impl OpCodeReader for SetRegisterRegister {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.gp_register(opcode_data.x).0 = state.gp_register(opcode_data.y).0;
+        Ok(())
     }
 }
 
@@ -304,8 +550,13 @@ impl OpCodeReader for BinaryOr {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("OR V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.gp_register(opcode_data.x).0 |= state.gp_register(opcode_data.y).0;
+        Ok(())
     }
 }
 
@@ -321,8 +572,13 @@ impl OpCodeReader for BinaryAnd {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("AND V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.gp_register(opcode_data.x).0 &= state.gp_register(opcode_data.y).0;
+        Ok(())
     }
 }
 
@@ -338,8 +594,13 @@ impl OpCodeReader for BinaryXor {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("XOR V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.gp_register(opcode_data.x).0 ^= state.gp_register(opcode_data.y).0;
+        Ok(())
     }
 }
 
@@ -355,13 +616,18 @@ impl OpCodeReader for AddRegisters {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("ADD V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         let x_reg_val = state.gp_register(opcode_data.x).0;
         let y_reg_val = state.gp_register(opcode_data.y).0;
         let sat_add = x_reg_val.saturating_add(y_reg_val);
         let wrap_add = x_reg_val.wrapping_add(y_reg_val);
         state.gp_register(opcode_data.x).0 = wrap_add;
         state.gp_register(0xF).0 = if sat_add != wrap_add { 0x1 } else { 0x0 };
+        Ok(())
     }
 }
 
@@ -377,11 +643,16 @@ impl OpCodeReader for SubtractRegisters {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SUB V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         let x_reg_val = state.gp_register(opcode_data.x).0;
         let y_reg_val = state.gp_register(opcode_data.y).0;
         state.gp_register(opcode_data.x).0 = x_reg_val.wrapping_sub(y_reg_val);
         state.gp_register(0xF).0 = if y_reg_val > x_reg_val { 0x0 } else { 0x1 };
+        Ok(())
     }
 }
 
@@ -397,11 +668,16 @@ impl OpCodeReader for SubtractRegistersReverse {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SUBN V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         let x_reg_val = state.gp_register(opcode_data.x).0;
         let y_reg_val = state.gp_register(opcode_data.y).0;
         state.gp_register(opcode_data.x).0 = y_reg_val.wrapping_sub(x_reg_val);
         state.gp_register(0xF).0 = if x_reg_val > y_reg_val { 0x0 } else { 0x1 };
+        Ok(())
     }
 }
 
@@ -417,11 +693,14 @@ impl OpCodeReader for ShiftRegisterRight {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let x_reg = state.gp_register(opcode_data.x);
-        let removed_bit = x_reg.0 & 0x01;
-        x_reg.0 >>= 1;
-        state.gp_register(0xF).0 = removed_bit;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SHR V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        let value = shift_source(state, &opcode_data);
+        write_shift_result(state, opcode_data.x, value >> 1, value & 0x01);
+        Ok(())
     }
 }
 
@@ -437,11 +716,37 @@ impl OpCodeReader for ShiftRegisterLeft {
         0xF00F
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let x_reg = state.gp_register(opcode_data.x);
-        let removed_bit = if (x_reg.0 & 0x80) == 0 { 0x00 } else { 0x01 };
-        x_reg.0 <<= 1;
-        state.gp_register(0xF).0 = removed_bit;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SHL V{:X}, V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        let value = shift_source(state, &opcode_data);
+        write_shift_result(state, opcode_data.x, value << 1, value >> 7);
+        Ok(())
+    }
+}
+
+/// The value a shift works on, picked by [`crate::quirks::QuirkConfig::shift_quirk`]
+fn shift_source(state: &mut Chip8State, opcode_data: &OpCodeData) -> u8 {
+    match state.quirks.shift_quirk {
+        ShiftQuirk::InPlace => state.gp_register(opcode_data.x).0,
+        ShiftQuirk::CopyY => state.gp_register(opcode_data.y).0,
+    }
+}
+
+/// Writes the outcome of a shift to VX and VF, in the order picked by
+/// [`crate::quirks::QuirkConfig::shift_flag_order`]. The order only matters when X is F.
+fn write_shift_result(state: &mut Chip8State, x: u8, result: u8, removed_bit: u8) {
+    match state.quirks.shift_flag_order {
+        ShiftFlagOrder::FlagLast => {
+            state.gp_register(x).0 = result;
+            state.gp_register(0xF).0 = removed_bit;
+        }
+        ShiftFlagOrder::ResultLast => {
+            state.gp_register(0xF).0 = removed_bit;
+            state.gp_register(x).0 = result;
+        }
     }
 }
 
@@ -457,8 +762,15 @@ impl OpCodeReader for JumpOffset {
         0xF000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        state.pc = Address(state.pc.0 + opcode_data.nnn + u16::from(state.gp_register(0x0).0));
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("JP V0, 0x{:03X}", opcode_data.nnn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        // The target replaces PC outright, it isn't relative to the current instruction
+        let offset = u16::from(state.gp_register(0x0).0);
+        state.pc = Address(opcode_data.nnn.wrapping_add(offset));
+        Ok(())
     }
 }
 
@@ -474,9 +786,24 @@ impl OpCodeReader for Random {
         0xF000
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        state.gp_register(opcode_data.x).0 = rand::random::<u8>() & opcode_data.nn;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("RND V{:X}, 0x{:02X}", opcode_data.x, opcode_data.nn)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        state.gp_register(opcode_data.x).0 = state.rng.next_u8() & opcode_data.nn;
+        Ok(())
+    }
+}
+
+/// Reads a key value out of VX. Values above 0xF are only valid when we're not being strict about
+/// them (see [`crate::quirks::QuirkConfig::strict_key_values`]).
+fn register_key(state: &mut Chip8State, x: u8) -> Result<u8, Fault> {
+    let key = state.gp_register(x).0;
+    if key > 0x0F && state.quirks.strict_key_values {
+        return Err(Fault::KeyOutOfRange(key));
     }
+    Ok(key)
 }
 
 #[derive(Debug, Default, Clone)]
@@ -491,11 +818,16 @@ impl OpCodeReader for SkipIfKey {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let key = state.gp_register(opcode_data.x).0;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SKP V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        let key = register_key(state, opcode_data.x)?;
         if state.is_pressed(key) {
             state.pc.0 += 2;
         }
+        Ok(())
     }
 }
 
@@ -511,12 +843,17 @@ impl OpCodeReader for SkipIfNotKey {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let key = state.gp_register(opcode_data.x).0;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SKNP V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        let key = register_key(state, opcode_data.x)?;
         if !state.is_pressed(key) {
             log::debug!("SkipIfNotKey: skipping (key {key:#x})");
             state.pc.0 += 2;
         }
+        Ok(())
     }
 }
 
@@ -532,8 +869,13 @@ impl OpCodeReader for ReadDelayTimer {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD V{:X}, DT", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.gp_register(opcode_data.x).0 = state.delay_timer.0;
+        Ok(())
     }
 }
 
@@ -549,8 +891,13 @@ impl OpCodeReader for SetDelayTimer {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD DT, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.delay_timer.0 = state.gp_register(opcode_data.x).0;
+        Ok(())
     }
 }
 
@@ -566,8 +913,13 @@ impl OpCodeReader for SetSoundTimer {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD ST, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.sound_timer.0 = state.gp_register(opcode_data.x).0;
+        Ok(())
     }
 }
 
@@ -583,10 +935,15 @@ impl OpCodeReader for AddIndexRegister {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("ADD I, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.index_register.0 += u16::from(state.gp_register(opcode_data.x).0);
         let overflows = state.index_register.0 > 0xFFF;
-        state.gp_register(0xF).0 = if overflows { 0x1 } else { 0x0 }
+        state.gp_register(0xF).0 = if overflows { 0x1 } else { 0x0 };
+        Ok(())
     }
 }
 
@@ -595,18 +952,25 @@ pub struct GetKey;
 
 impl OpCodeReader for GetKey {
     fn opcode_val(&self) -> u16 {
-        0xF01A
+        0xF00A
     }
 
     fn opcode_mask(&self) -> u16 {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let key = state.gp_register(opcode_data.x).0;
-        if !state.is_pressed(key) {
-            state.pc.0 -= 2;
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD V{:X}, K", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        // If several keys are held we always pick the lowest one (see `KeyInput::first_pressed`)
+        match state.key_state.first_pressed() {
+            Some(key) => state.gp_register(opcode_data.x).0 = key,
+            // Nothing pressed yet, so we "block" by executing this instruction again
+            None => state.pc.0 -= 2,
         }
+        Ok(())
     }
 }
 
@@ -622,8 +986,36 @@ impl OpCodeReader for ReadFontCharacter {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD F, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         state.index_register.0 = 0x50 + (u16::from(state.gp_register(opcode_data.x).0) * 0x5);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReadLargeFontCharacter;
+
+impl OpCodeReader for ReadLargeFontCharacter {
+    fn opcode_val(&self) -> u16 {
+        0xF030
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xF0FF
+    }
+
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD HF, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        state.index_register.0 =
+            LARGE_FONT_START + (u16::from(state.gp_register(opcode_data.x).0) * 10);
+        Ok(())
     }
 }
 
@@ -639,14 +1031,21 @@ impl OpCodeReader for DecimalDecoding {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD B, V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         let register_val = state.gp_register(opcode_data.x).0;
         let digits = [
             register_val / 100,
             (register_val % 100) / 10,
             register_val % 10,
         ];
-        state.memory_set(&digits, state.index_register);
+        for (offset, digit) in digits.into_iter().enumerate() {
+            state.set_mem_at_index(offset as u16, digit)?;
+        }
+        Ok(())
     }
 }
 
@@ -662,11 +1061,17 @@ impl OpCodeReader for StoreMemory {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let address_start = usize::from(state.index_register.0);
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD [I], V{:X}", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         for reg in 0..=opcode_data.x {
-            state.memory[address_start + usize::from(reg)] = state.gp_register(reg).0;
+            let value = state.gp_register(reg).0;
+            state.set_mem_at_index(reg.into(), value)?;
         }
+        increment_index_after_memory_access(state, opcode_data.x);
+        Ok(())
     }
 }
 
@@ -682,11 +1087,223 @@ impl OpCodeReader for LoadMemory {
         0xF0FF
     }
 
-    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) {
-        let address_start = usize::from(state.index_register.0);
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LD V{:X}, [I]", opcode_data.x)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
         for reg in 0..=opcode_data.x {
-            state.gp_register(reg).0 = state.memory[address_start + usize::from(reg)];
+            let byte = state.mem_at_index(reg.into())?;
+            state.gp_register(reg).0 = byte;
         }
+        increment_index_after_memory_access(state, opcode_data.x);
+        Ok(())
+    }
+}
+
+/// Moves I past the registers `FX55`/`FX65` went through, if
+/// [`crate::quirks::QuirkConfig::memory_increment`] asks for it
+fn increment_index_after_memory_access(state: &mut Chip8State, x: u8) {
+    match state.quirks.memory_increment {
+        MemoryIncrementQuirk::Increment => state.index_register += u16::from(x) + 1,
+        MemoryIncrementQuirk::Leave => {}
+    }
+}
+
+/// Registers from VX to VY, in that order. XO-CHIP counts down when X is greater than Y, so e.g.
+/// `5312` goes V3, V2, V1 rather than being a no-op.
+fn register_range(x: u8, y: u8) -> Vec<u8> {
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
+}
+
+/// XO-CHIP's `5XY2`: stores VX to VY at I onwards, without changing I
+#[derive(Debug, Default, Clone)]
+pub struct StoreRegisterRange;
+
+impl OpCodeReader for StoreRegisterRange {
+    fn opcode_val(&self) -> u16 {
+        0x5002
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xF00F
+    }
+
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SAVE V{:X} - V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        for (offset, reg) in register_range(opcode_data.x, opcode_data.y)
+            .into_iter()
+            .enumerate()
+        {
+            let value = state.gp_register(reg).0;
+            state.set_mem_at_index(offset as u16, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// XO-CHIP's `5XY3`: loads VX to VY from I onwards, without changing I
+#[derive(Debug, Default, Clone)]
+pub struct LoadRegisterRange;
+
+impl OpCodeReader for LoadRegisterRange {
+    fn opcode_val(&self) -> u16 {
+        0x5003
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xF00F
+    }
+
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("LOAD V{:X} - V{:X}", opcode_data.x, opcode_data.y)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        for (offset, reg) in register_range(opcode_data.x, opcode_data.y)
+            .into_iter()
+            .enumerate()
+        {
+            let byte = state.mem_at_index(offset as u16)?;
+            state.gp_register(reg).0 = byte;
+        }
+        Ok(())
+    }
+}
+
+/// SUPER-CHIP counts scroll distances in high resolution pixels, so in low resolution the display
+/// only moves half as far
+fn scroll_distance(state: &Chip8State, pixels: usize) -> usize {
+    if state.display.resolution() == HIGH_RES {
+        pixels
+    } else {
+        pixels / 2
+    }
+}
+
+/// SUPER-CHIP's `00CN`: scrolls the display down by N pixels, or N/2 in low resolution (see
+/// [`scroll_distance`])
+#[derive(Debug, Default, Clone)]
+pub struct ScrollDown;
+
+impl OpCodeReader for ScrollDown {
+    fn opcode_val(&self) -> u16 {
+        0x00C0
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xfff0
+    }
+
+    fn mnemonic(&self, opcode_data: &OpCodeData) -> String {
+        format!("SCD {}", opcode_data.n)
+    }
+
+    fn execute(&self, state: &mut Chip8State, opcode_data: OpCodeData) -> Result {
+        let rows = scroll_distance(state, usize::from(opcode_data.n));
+        state.display.scroll_down(rows);
+        Ok(())
+    }
+}
+
+/// How far the SUPER-CHIP horizontal scrolls move the display, in high resolution pixels (see
+/// [`scroll_distance`])
+const HORIZONTAL_SCROLL: usize = 4;
+
+#[derive(Debug, Default, Clone)]
+pub struct ScrollRight;
+
+impl OpCodeReader for ScrollRight {
+    fn opcode_val(&self) -> u16 {
+        0x00FB
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xffff
+    }
+
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "SCR".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _: OpCodeData) -> Result {
+        let columns = scroll_distance(state, HORIZONTAL_SCROLL);
+        state.display.scroll_right(columns);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ScrollLeft;
+
+impl OpCodeReader for ScrollLeft {
+    fn opcode_val(&self) -> u16 {
+        0x00FC
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xffff
+    }
+
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "SCL".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _: OpCodeData) -> Result {
+        let columns = scroll_distance(state, HORIZONTAL_SCROLL);
+        state.display.scroll_left(columns);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LowRes;
+
+impl OpCodeReader for LowRes {
+    fn opcode_val(&self) -> u16 {
+        0x00FE
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xffff
+    }
+
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "LOW".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _: OpCodeData) -> Result {
+        state.display.set_resolution(SCREEN_RES);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct HighRes;
+
+impl OpCodeReader for HighRes {
+    fn opcode_val(&self) -> u16 {
+        0x00FF
+    }
+
+    fn opcode_mask(&self) -> u16 {
+        0xffff
+    }
+
+    fn mnemonic(&self, _: &OpCodeData) -> String {
+        "HIGH".to_string()
+    }
+
+    fn execute(&self, state: &mut Chip8State, _: OpCodeData) -> Result {
+        state.display.set_resolution(HIGH_RES);
+        Ok(())
     }
 }
 
@@ -694,13 +1311,37 @@ impl OpCodeReader for LoadMemory {
 mod test {
     use super::*;
     use crate::{
-        display::{Coordinates, Display},
-        emulator::{Address, Register},
+        diagnostics::Diagnostics,
+        display::{Coordinates, Display, Resolution},
+        emulator::{Address, Error, Register},
+        quirks::QuirkConfig,
+        stack::Stack,
     };
     use expect_test::expect;
-    use std::collections::VecDeque;
     use test_case::test_case;
 
+    #[test]
+    fn test_decode_table_matches_linear_lookup() {
+        let instructions = supported_instructions();
+        let table = DecodeTable::default();
+        for opcode in 0..=u16::MAX {
+            let expected = linear_lookup(&instructions, opcode).map(|i| format!("{i:?}"));
+            let actual = table.lookup(opcode).map(|i| format!("{i:?}"));
+            assert_eq!(
+                actual, expected,
+                "opcode {opcode:#06x} resolved differently"
+            );
+        }
+    }
+
+    #[test_case(0xF50A, Some("GetKey"); "get_key")]
+    #[test_case(0xF51A, None; "old_get_key_value")]
+    fn test_decode_table_dispatch(opcode: u16, expected: Option<&str>) {
+        let table = DecodeTable::default();
+        let actual = table.lookup(opcode).map(|i| format!("{i:?}"));
+        assert_eq!(actual.as_deref(), expected);
+    }
+
     #[test]
     fn test_decode() {
         let decoded = OpCodeData::decode(0x1B3D);
@@ -766,7 +1407,9 @@ mod test {
                     *pixel = rand::random();
                 }
             }
-            cs_reader.execute(&mut state, OpCodeData::decode(0x00e0));
+            cs_reader
+                .execute(&mut state, OpCodeData::decode(0x00e0))
+                .unwrap();
             expected_screen.assert_eq(&state.display.to_string());
         }
     }
@@ -776,7 +1419,9 @@ mod test {
         let jump_reader = Jump;
         let mut state = Chip8State::new().with_pc(Address(100));
         let correct_state = state.clone().with_pc(Address(0x1de));
-        jump_reader.execute(&mut state, OpCodeData::decode(0x11de));
+        jump_reader
+            .execute(&mut state, OpCodeData::decode(0x11de))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -785,7 +1430,9 @@ mod test {
         let sr_reader = SetRegisterConst;
         let mut state = Chip8State::new().with_register(Register(0xef), 2);
         let correct_state = state.clone().with_register(Register(0x12), 2);
-        sr_reader.execute(&mut state, OpCodeData::decode(0x6212));
+        sr_reader
+            .execute(&mut state, OpCodeData::decode(0x6212))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -794,7 +1441,9 @@ mod test {
         let ar_reader = AddRegisterConst;
         let mut state = Chip8State::new().with_register(Register(0x43), 0x0a);
         let correct_state = state.clone().with_register(Register(0x7d), 0x0a);
-        ar_reader.execute(&mut state, OpCodeData::decode(0x7a3a));
+        ar_reader
+            .execute(&mut state, OpCodeData::decode(0x7a3a))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -803,7 +1452,9 @@ mod test {
         let sir_reader = SetIndexRegister;
         let mut state = Chip8State::new().with_index_register(Address(0x001));
         let correct_state = state.clone().with_index_register(Address(0x0123));
-        sir_reader.execute(&mut state, OpCodeData::decode(0xA123));
+        sir_reader
+            .execute(&mut state, OpCodeData::decode(0xA123))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -815,7 +1466,9 @@ mod test {
             .clone()
             .with_pc(Address(0x123))
             .with_stack([Address(0x100)].into_iter().collect());
-        subroutine_call_reader.execute(&mut state, OpCodeData::decode(0x2123));
+        subroutine_call_reader
+            .execute(&mut state, OpCodeData::decode(0x2123))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -828,8 +1481,10 @@ mod test {
         let correct_state = state
             .clone()
             .with_pc(Address(0x100))
-            .with_stack(VecDeque::default());
-        subroutine_return_reader.execute(&mut state, OpCodeData::decode(0x00EE));
+            .with_stack(Stack::default());
+        subroutine_return_reader
+            .execute(&mut state, OpCodeData::decode(0x00EE))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -844,14 +1499,18 @@ mod test {
         // Case with skip
         {
             let mut state = original_state.clone();
-            skip_const_equal_reader.execute(&mut state, OpCodeData::decode(0x335A));
+            skip_const_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x335A))
+                .unwrap();
             assert_eq!(state, skip_state);
         }
 
         // Case without skip
         {
             let mut state = original_state.clone();
-            skip_const_equal_reader.execute(&mut state, OpCodeData::decode(0x334A));
+            skip_const_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x334A))
+                .unwrap();
             assert_eq!(state, original_state);
         }
     }
@@ -867,14 +1526,18 @@ mod test {
         // Case with skip
         {
             let mut state = original_state.clone();
-            skip_const_not_equal_reader.execute(&mut state, OpCodeData::decode(0x434A));
+            skip_const_not_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x434A))
+                .unwrap();
             assert_eq!(state, skip_state);
         }
 
         // Case without skip
         {
             let mut state = original_state.clone();
-            skip_const_not_equal_reader.execute(&mut state, OpCodeData::decode(0x435A));
+            skip_const_not_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x435A))
+                .unwrap();
             assert_eq!(state, original_state);
         }
     }
@@ -892,14 +1555,18 @@ mod test {
         // Case with skip
         {
             let mut state = original_state.clone();
-            skip_registers_equal_reader.execute(&mut state, OpCodeData::decode(0x5340));
+            skip_registers_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x5340))
+                .unwrap();
             assert_eq!(state, skip_state);
         }
 
         // Case without skip
         {
             let mut state = original_state.clone();
-            skip_registers_equal_reader.execute(&mut state, OpCodeData::decode(0x5350));
+            skip_registers_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x5350))
+                .unwrap();
             assert_eq!(state, original_state);
         }
     }
@@ -917,14 +1584,18 @@ mod test {
         // Case with skip
         {
             let mut state = original_state.clone();
-            skip_registers_not_equal_reader.execute(&mut state, OpCodeData::decode(0x9350));
+            skip_registers_not_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x9350))
+                .unwrap();
             assert_eq!(state, skip_state);
         }
 
         // Case without skip
         {
             let mut state = original_state.clone();
-            skip_registers_not_equal_reader.execute(&mut state, OpCodeData::decode(0x9340));
+            skip_registers_not_equal_reader
+                .execute(&mut state, OpCodeData::decode(0x9340))
+                .unwrap();
             assert_eq!(state, original_state);
         }
     }
@@ -937,7 +1608,9 @@ mod test {
             .with_register(Register(0x9C), 0x2)
             .with_register(Register(0xC6), 0x3);
         let correct_state = state.clone().with_register(Register(0xC6), 0x02);
-        set_register_register_reader.execute(&mut state, OpCodeData::decode(0x8230));
+        set_register_register_reader
+            .execute(&mut state, OpCodeData::decode(0x8230))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -949,7 +1622,9 @@ mod test {
             .with_register(Register(0x9C), 0x2)
             .with_register(Register(0xC6), 0x3);
         let correct_state = state.clone().with_register(Register(0xDE), 0x02);
-        binary_or_reader.execute(&mut state, OpCodeData::decode(0x8231));
+        binary_or_reader
+            .execute(&mut state, OpCodeData::decode(0x8231))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -961,7 +1636,9 @@ mod test {
             .with_register(Register(0x9C), 0x2)
             .with_register(Register(0xC6), 0x3);
         let correct_state = state.clone().with_register(Register(0x84), 0x02);
-        binary_and_reader.execute(&mut state, OpCodeData::decode(0x8232));
+        binary_and_reader
+            .execute(&mut state, OpCodeData::decode(0x8232))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -973,7 +1650,9 @@ mod test {
             .with_register(Register(0x9C), 0x2)
             .with_register(Register(0xC6), 0x3);
         let correct_state = state.clone().with_register(Register(0x5A), 0x02);
-        binary_xor_reader.execute(&mut state, OpCodeData::decode(0x8233));
+        binary_xor_reader
+            .execute(&mut state, OpCodeData::decode(0x8233))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -992,7 +1671,9 @@ mod test {
             .clone()
             .with_register(Register(result), 0x2)
             .with_register(Register(if overflows { 0x01 } else { 0x00 }), 0xF);
-        add_registers_reader.execute(&mut state, OpCodeData::decode(0x8234));
+        add_registers_reader
+            .execute(&mut state, OpCodeData::decode(0x8234))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1012,7 +1693,9 @@ mod test {
             .clone()
             .with_register(Register(result), 0x2)
             .with_register(Register(if underflows { 0x01 } else { 0x00 }), 0xF);
-        subtract_registers_reader.execute(&mut state, OpCodeData::decode(0x8235));
+        subtract_registers_reader
+            .execute(&mut state, OpCodeData::decode(0x8235))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1032,7 +1715,9 @@ mod test {
             .clone()
             .with_register(Register(result), 0x2)
             .with_register(Register(if underflows { 0x01 } else { 0x00 }), 0xF);
-        subtract_registers_reverse_reader.execute(&mut state, OpCodeData::decode(0x8237));
+        subtract_registers_reverse_reader
+            .execute(&mut state, OpCodeData::decode(0x8237))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1049,7 +1734,9 @@ mod test {
             .clone()
             .with_register(Register(result), 0x7)
             .with_register(Register(if bit_shifted { 0x01 } else { 0x00 }), 0xF);
-        shift_register_right_reader.execute(&mut state, OpCodeData::decode(0x8706));
+        shift_register_right_reader
+            .execute(&mut state, OpCodeData::decode(0x8706))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1066,21 +1753,153 @@ mod test {
             .clone()
             .with_register(Register(result), 0x7)
             .with_register(Register(if bit_shifted { 0x01 } else { 0x00 }), 0xF);
-        shift_register_left_reader.execute(&mut state, OpCodeData::decode(0x870E));
+        shift_register_left_reader
+            .execute(&mut state, OpCodeData::decode(0x870E))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
-    #[test]
-    fn test_jump_offset() {
+    // V7 holds 0x59 and V3 holds 0x9C
+    #[test_case(ShiftQuirk::InPlace, 0x8736, 0x2C, 0x01; "right_in_place")]
+    #[test_case(ShiftQuirk::CopyY,   0x8736, 0x4E, 0x00; "right_copy_y")]
+    #[test_case(ShiftQuirk::InPlace, 0x873E, 0xB2, 0x00; "left_in_place")]
+    #[test_case(ShiftQuirk::CopyY,   0x873E, 0x38, 0x01; "left_copy_y")]
+    fn test_shift_quirk(shift_quirk: ShiftQuirk, opcode: u16, result: u8, vf_value: u8) {
+        let mut state = Chip8State::new()
+            .with_quirks(QuirkConfig {
+                shift_quirk,
+                ..QuirkConfig::default()
+            })
+            .with_register(Register(0x59), 0x7)
+            .with_register(Register(0x9C), 0x3);
+        // VY is only ever read from
+        let correct_state = state
+            .clone()
+            .with_register(Register(result), 0x7)
+            .with_register(Register(vf_value), 0xF);
+        let instructions = supported_instructions();
+        linear_lookup(&instructions, opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    #[test_case(ShiftFlagOrder::FlagLast,   0x8F06, 0x01; "right_flag_last")]
+    #[test_case(ShiftFlagOrder::ResultLast, 0x8F06, 0x2C; "right_result_last")]
+    #[test_case(ShiftFlagOrder::FlagLast,   0x8F0E, 0x00; "left_flag_last")]
+    #[test_case(ShiftFlagOrder::ResultLast, 0x8F0E, 0xB2; "left_result_last")]
+    fn test_shift_vf(shift_flag_order: ShiftFlagOrder, opcode: u16, vf_value: u8) {
+        let mut state = Chip8State::new()
+            .with_quirks(QuirkConfig {
+                shift_flag_order,
+                ..QuirkConfig::default()
+            })
+            .with_register(Register(0x59), 0xF);
+        let correct_state = state.clone().with_register(Register(vf_value), 0xF);
+        let instructions = supported_instructions();
+        linear_lookup(&instructions, opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    #[test_case(false, 0x01; "quirk_off")]
+    #[test_case(true, 0x00; "quirk_on")]
+    fn test_clear_screen_vf(cls_resets_vf: bool, vf_value: u8) {
+        let mut state = Chip8State::new()
+            .with_quirks(QuirkConfig {
+                cls_resets_vf,
+                ..QuirkConfig::default()
+            })
+            .with_register(Register(0x01), 0xF);
+        let correct_state = state.clone().with_register(Register(vf_value), 0xF);
+        ClearScreen
+            .execute(&mut state, OpCodeData::decode(0x00E0))
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    #[test_case(0xB154, 0x12, 0x166; "small_offset")]
+    #[test_case(0xBFFF, 0xFF, 0x10FE; "largest_target")]
+    fn test_jump_offset(opcode: u16, v0: u8, expected_pc: u16) {
         let jump_offset_reader = JumpOffset;
+        // The current PC mustn't factor into where we end up
         let mut state = Chip8State::new()
-            .with_pc(Address(0x100))
-            .with_register(Register(0x12), 0x0);
-        let correct_state = state.clone().with_pc(Address(0x266));
-        jump_offset_reader.execute(&mut state, OpCodeData::decode(0xB154));
+            .with_pc(Address(0x400))
+            .with_register(Register(v0), 0x0);
+        let correct_state = state.clone().with_pc(Address(expected_pc));
+        jump_offset_reader
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
+    /// Runs `CXNN` with the given mask over and over on a seeded state, returning every result
+    fn random_values(mask: u8, seed: u64, count: usize) -> Vec<u8> {
+        let mut state = Chip8State::new().with_seed(seed);
+        let opcode = 0xC300 | u16::from(mask);
+        (0..count)
+            .map(|_| {
+                Random
+                    .execute(&mut state, OpCodeData::decode(opcode))
+                    .unwrap();
+                state.gp_registers[0x3].0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_random_zero_mask() {
+        assert!(random_values(0x00, 7, 1000).iter().all(|value| *value == 0));
+    }
+
+    #[test]
+    fn test_random_seeded() {
+        assert_eq!(random_values(0xFF, 7, 64), random_values(0xFF, 7, 64));
+        assert_ne!(random_values(0xFF, 7, 64), random_values(0xFF, 8, 64));
+        // Masking happens after the RNG, so the same seed gives the same bits under any mask
+        let masked: Vec<u8> = random_values(0xFF, 7, 64)
+            .into_iter()
+            .map(|value| value & 0x0F)
+            .collect();
+        assert_eq!(random_values(0x0F, 7, 64), masked);
+    }
+
+    #[test_case(0xFF; "full_byte")]
+    #[test_case(0x0F; "low_nibble")]
+    #[test_case(0xA5; "sparse")]
+    fn test_random_distribution(mask: u8) {
+        const SAMPLES: usize = 20_000;
+        let values = random_values(mask, 0xC8, SAMPLES);
+        assert!(values.iter().all(|value| value & !mask == 0));
+
+        // Each unmasked bit should be set about half the time
+        for bit in (0..8).filter(|bit| mask & (1 << bit) != 0) {
+            let set = values
+                .iter()
+                .filter(|value| *value & (1 << bit) != 0)
+                .count();
+            let ratio = set as f64 / SAMPLES as f64;
+            assert!(
+                (0.48..0.52).contains(&ratio),
+                "bit {bit} set {ratio} of the time"
+            );
+        }
+        // And with the full byte, every value should turn up about equally often
+        if mask == 0xFF {
+            let mut counts = [0usize; 0x100];
+            for value in &values {
+                counts[usize::from(*value)] += 1;
+            }
+            let expected = SAMPLES / 0x100;
+            assert!(counts
+                .iter()
+                .all(|count| (expected / 2..expected * 2).contains(count)));
+        }
+    }
+
     #[test_case(0xA, 0x1, 0x100; "wrong_key_pressed")]
     #[test_case(0xF, 0xF, 0x102; "key_pressed")]
     fn test_skip_if_key(key_pressed: u8, key_checked: u8, expected_pc: u16) {
@@ -1090,10 +1909,35 @@ mod test {
             .with_key_pressed(key_pressed)
             .with_register(Register(key_checked), 0x5);
         let correct_state = state.clone().with_pc(Address(expected_pc));
-        skip_if_key_reader.execute(&mut state, OpCodeData::decode(0xE59E));
+        skip_if_key_reader
+            .execute(&mut state, OpCodeData::decode(0xE59E))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
+    #[test_case(false, None; "masked")]
+    #[test_case(true, Some(Fault::KeyOutOfRange(0x25)); "strict")]
+    fn test_skip_if_key_out_of_range(strict_key_values: bool, fault: Option<Fault>) {
+        let skip_if_key_reader = SkipIfKey;
+        let mut state = Chip8State::new()
+            .with_quirks(QuirkConfig {
+                strict_key_values,
+                ..QuirkConfig::default()
+            })
+            .with_pc(Address(0x100))
+            .with_key_pressed(0x5)
+            .with_register(Register(0x25), 0x5);
+        let result = skip_if_key_reader.execute(&mut state, OpCodeData::decode(0xE59E));
+        match fault {
+            Some(fault) => assert!(matches!(result, Err(Error::Fault(f)) if f == fault)),
+            None => {
+                result.unwrap();
+                // 0x25 is read as key 0x5, which is pressed
+                assert_eq!(state.pc, Address(0x102));
+            }
+        }
+    }
+
     #[test_case(0xA, 0x1, 0x102; "key_not_pressed")]
     #[test_case(0xF, 0xF, 0x100; "key_pressed")]
     fn test_skip_if_not_key(key_pressed: u8, key_checked: u8, expected_pc: u16) {
@@ -1103,7 +1947,9 @@ mod test {
             .with_key_pressed(key_pressed)
             .with_register(Register(key_checked), 0x5);
         let correct_state = state.clone().with_pc(Address(expected_pc));
-        skip_if_not_key_reader.execute(&mut state, OpCodeData::decode(0xE59E));
+        skip_if_not_key_reader
+            .execute(&mut state, OpCodeData::decode(0xE59E))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1112,7 +1958,9 @@ mod test {
         let read_delay_timer_reader = ReadDelayTimer;
         let mut state = Chip8State::new().with_delay_timer(Register(0x9F));
         let correct_state = state.clone().with_register(Register(0x9F), 0x5);
-        read_delay_timer_reader.execute(&mut state, OpCodeData::decode(0xF507));
+        read_delay_timer_reader
+            .execute(&mut state, OpCodeData::decode(0xF507))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1121,7 +1969,9 @@ mod test {
         let set_delay_timer_reader = SetDelayTimer;
         let mut state = Chip8State::new().with_register(Register(0x9F), 0x4);
         let correct_state = state.clone().with_delay_timer(Register(0x9F));
-        set_delay_timer_reader.execute(&mut state, OpCodeData::decode(0xF415));
+        set_delay_timer_reader
+            .execute(&mut state, OpCodeData::decode(0xF415))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1130,7 +1980,9 @@ mod test {
         let set_sound_timer_reader = SetSoundTimer;
         let mut state = Chip8State::new().with_register(Register(0x9F), 0x2);
         let correct_state = state.clone().with_sound_timer(Register(0x9F));
-        set_sound_timer_reader.execute(&mut state, OpCodeData::decode(0xF218));
+        set_sound_timer_reader
+            .execute(&mut state, OpCodeData::decode(0xF218))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1145,20 +1997,29 @@ mod test {
             .clone()
             .with_index_register(Address(result))
             .with_register(Register(if overflows { 0x1 } else { 0x0 }), 0xF);
-        add_index_register_reader.execute(&mut state, OpCodeData::decode(0xFA1E));
+        add_index_register_reader
+            .execute(&mut state, OpCodeData::decode(0xFA1E))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
-    #[test_case(0xD, 0x2, 0xFE; "key_not_pressed")]
-    #[test_case(0x5, 0x5, 0x100; "key_pressed")]
-    fn test_get_key(pressed_key: u8, checked_key: u8, expected_pc: u16) {
+    #[test_case(&[], 0x00, 0xFE; "key_not_pressed")]
+    #[test_case(&[0x5], 0x05, 0x100; "key_pressed")]
+    #[test_case(&[0xC], 0x0C, 0x100; "key_c_pressed")]
+    #[test_case(&[0x9, 0x3], 0x03, 0x100; "lowest_key_wins")]
+    fn test_get_key(pressed_keys: &[u8], expected_vx: u8, expected_pc: u16) {
         let get_key_reader = GetKey;
-        let mut state = Chip8State::new()
-            .with_pc(Address(0x100))
-            .with_key_pressed(pressed_key)
-            .with_register(Register(checked_key), 0xD);
-        let correct_state = state.clone().with_pc(Address(expected_pc));
-        get_key_reader.execute(&mut state, OpCodeData::decode(0xFD0A));
+        let mut state = Chip8State::new().with_pc(Address(0x100));
+        for key in pressed_keys {
+            state = state.with_key_pressed(*key);
+        }
+        let correct_state = state
+            .clone()
+            .with_pc(Address(expected_pc))
+            .with_register(Register(expected_vx), 0xD);
+        get_key_reader
+            .execute(&mut state, OpCodeData::decode(0xFD0A))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1169,7 +2030,22 @@ mod test {
         // We expect our font to be loaded starting at address 0x50, and each "character" is 5
         // bytes long, so 0x50 + (0x7 * 0x5) = 0x73
         let correct_state = state.clone().with_index_register(Address(0x073));
-        read_font_character_reader.execute(&mut state, OpCodeData::decode(0xFB29));
+        read_font_character_reader
+            .execute(&mut state, OpCodeData::decode(0xFB29))
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    // The large font starts at 0xA0, and each digit is 10 bytes long
+    #[test_case(0x0, 0x0A0; "zero")]
+    #[test_case(0x7, 0x0E6; "seven")]
+    #[test_case(0x9, 0x0FA; "nine")]
+    fn test_read_large_font_character(digit: u8, address: u16) {
+        let mut state = Chip8State::new().with_register(Register(digit), 0x3);
+        let correct_state = state.clone().with_index_register(Address(address));
+        ReadLargeFontCharacter
+            .execute(&mut state, OpCodeData::decode(0xF330))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1182,7 +2058,9 @@ mod test {
             .with_register(Register(value), 0x8)
             .with_index_register(Address(address));
         let correct_state = state.clone().with_memory_set(digits, Address(address));
-        decimal_decoding_reader.execute(&mut state, OpCodeData::decode(0xF833));
+        decimal_decoding_reader
+            .execute(&mut state, OpCodeData::decode(0xF833))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
@@ -1217,13 +2095,34 @@ mod test {
         let correct_state = state
             .clone()
             .with_memory_set(&SAMPLE_DATA[..=usize::from(register)], Address(address));
-        store_memory_reader.execute(
-            &mut state,
-            OpCodeData::decode(0xF055 + u16::from(register) * 0x100),
-        );
+        store_memory_reader
+            .execute(
+                &mut state,
+                OpCodeData::decode(0xF055 + u16::from(register) * 0x100),
+            )
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
+    #[test_case(MemoryIncrementQuirk::Leave, 0xF355, 0x300; "store_leave")]
+    #[test_case(MemoryIncrementQuirk::Increment, 0xF355, 0x304; "store_increment")]
+    #[test_case(MemoryIncrementQuirk::Leave, 0xF065, 0x300; "load_leave")]
+    #[test_case(MemoryIncrementQuirk::Increment, 0xF065, 0x301; "load_increment")]
+    fn test_memory_increment(memory_increment: MemoryIncrementQuirk, opcode: u16, index: u16) {
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_quirks(QuirkConfig {
+                memory_increment,
+                ..QuirkConfig::default()
+            });
+        let instructions = supported_instructions();
+        linear_lookup(&instructions, opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert_eq!(state.index_register, Address(index));
+    }
+
     #[test_case(0x123, 0x5; "six_bytes")]
     #[test_case(0xFFF, 0x0; "one_byte")]
     #[test_case(0x500, 0xF; "all_bytes")]
@@ -1237,13 +2136,93 @@ mod test {
             correct_state =
                 correct_state.with_register(Register(SAMPLE_DATA[usize::from(reg)]), reg);
         }
-        load_memory_reader.execute(
-            &mut state,
-            OpCodeData::decode(0xF065 + u16::from(register) * 0x100),
-        );
+        load_memory_reader
+            .execute(
+                &mut state,
+                OpCodeData::decode(0xF065 + u16::from(register) * 0x100),
+            )
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    #[test_case(0x5242, &[(0x2, 0x22), (0x3, 0x33), (0x4, 0x44)]; "ascending")]
+    #[test_case(0x5422, &[(0x4, 0x44), (0x3, 0x33), (0x2, 0x22)]; "descending")]
+    #[test_case(0x5332, &[(0x3, 0x33)]; "single_register")]
+    fn test_store_register_range(opcode: u16, stored: &[(u8, u8)]) {
+        let mut state = Chip8State::new().with_index_register(Address(0x300));
+        for reg in 0..=0xF {
+            state = state.with_register(Register(reg * 0x11), reg);
+        }
+        let mut correct_state = state.clone();
+        let bytes: Vec<u8> = stored.iter().map(|(_, value)| *value).collect();
+        correct_state.memory[0x300..0x300 + bytes.len()].copy_from_slice(&bytes);
+
+        StoreRegisterRange
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert_eq!(state, correct_state);
+    }
+
+    #[test_case(0x5243, &[(0x2, 0xA0), (0x3, 0xA1), (0x4, 0xA2)]; "ascending")]
+    #[test_case(0x5423, &[(0x4, 0xA0), (0x3, 0xA1), (0x2, 0xA2)]; "descending")]
+    #[test_case(0x5333, &[(0x3, 0xA0)]; "single_register")]
+    fn test_load_register_range(opcode: u16, loaded: &[(u8, u8)]) {
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[0xA0, 0xA1, 0xA2, 0xA3], Address(0x300));
+        let mut correct_state = state.clone();
+        for (reg, value) in loaded {
+            correct_state = correct_state.with_register(Register(*value), *reg);
+        }
+
+        LoadRegisterRange
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
         assert_eq!(state, correct_state);
     }
 
+    // I is 0xFFE, so each of these reaches 0xFFE, 0xFFF and then one byte past the end
+    #[test_case(0xD013; "display_draw")]
+    #[test_case(0xF033; "decimal_decoding")]
+    #[test_case(0xF255; "store_memory")]
+    #[test_case(0xF265; "load_memory")]
+    fn test_index_past_end_of_memory(opcode: u16) {
+        let instructions = supported_instructions();
+        let mut state = Chip8State::new().with_index_register(Address(0xFFE));
+        let result = linear_lookup(&instructions, opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode));
+        assert!(matches!(
+            result,
+            Err(Error::Fault(Fault::IndexOutOfRange {
+                index: Address(0xFFE),
+                offset: 2
+            }))
+        ));
+    }
+
+    #[test_case(0xD113, |state| state.display.pixels[2][0]; "display_draw")]
+    #[test_case(0xF033, |state| state.memory[0x000] == 0x03; "decimal_decoding")]
+    #[test_case(0xF255, |state| state.memory[0x000] == 0x7B; "store_memory")]
+    #[test_case(0xF265, |state| state.gp_registers[0x2] == Register(0x80); "load_memory")]
+    fn test_index_wraps(opcode: u16, wrapped: fn(&Chip8State) -> bool) {
+        let instructions = supported_instructions();
+        let mut state = Chip8State::new()
+            .with_quirks(QuirkConfig {
+                index_wraps: true,
+                ..QuirkConfig::default()
+            })
+            .with_memory_set(&[0x80], Address(0x000))
+            .with_index_register(Address(0xFFE))
+            .with_register(Register(123), 0x0)
+            .with_register(Register(123), 0x2);
+        linear_lookup(&instructions, opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert!(wrapped(&state));
+    }
+
     #[test]
     fn test_display_draw_basic() {
         let mut state = get_draw_state();
@@ -1256,7 +2235,9 @@ mod test {
         //|██████████  |
         //[etc.]
         // Basic case, confirm it gets drawn
-        d_reader.execute(&mut state, OpCodeData::decode(0xD233));
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
 
         let after_screen = expect![[r#"
             .----------------------------------------------------------------.
@@ -1296,6 +2277,234 @@ mod test {
         after_screen.assert_eq(&state.display.to_string());
     }
 
+    #[test_case(8, 0x01; "overlaps_box")]
+    #[test_case(0, 0x00; "empty_space")]
+    fn test_display_draw_collision(y: u8, vf_value: u8) {
+        let d_reader = DisplayDraw;
+        let mut state = get_draw_state()
+            .with_register(Register(y), 3)
+            .with_register(Register(0xAA), 0xF);
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
+        assert_eq!(state.gp_register(0xF).0, vf_value);
+    }
+
+    // VF only gets set when a lit pixel is turned off, never for drawing onto empty space
+    #[test_case(0b0000_0000, 0b1111_0000, 0x00; "onto_empty_space")]
+    #[test_case(0b1111_0000, 0b1111_0000, 0x01; "erases_lit_pixels")]
+    #[test_case(0b0011_0000, 0b1111_0000, 0x01; "sets_and_erases")]
+    #[test_case(0b0000_1111, 0b1111_0000, 0x00; "next_to_lit_pixels")]
+    fn test_display_draw_collision_on_erase(lit: u8, sprite: u8, vf_value: u8) {
+        let mut display = Display::default();
+        for (x, pixel) in display.pixels[0][..8].iter_mut().enumerate() {
+            *pixel = lit & (0x80 >> x) != 0;
+        }
+        let mut state = Chip8State::new()
+            .with_display(display)
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[sprite], Address(0x300))
+            .with_register(Register(0xAA), 0xF);
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD001))
+            .unwrap();
+        assert_eq!(state.gp_register(0xF).0, vf_value);
+        for (x, pixel) in state.display.pixels[0][..8].iter().enumerate() {
+            assert_eq!(*pixel, (lit ^ sprite) & (0x80 >> x) != 0);
+        }
+    }
+
+    // Scrolling by 4 only moves half as far in low resolution
+    #[test_case(SCREEN_RES, 2; "low_res")]
+    #[test_case(HIGH_RES, 4; "high_res")]
+    fn test_scroll_down(resolution: Resolution, scrolled: usize) {
+        // A diagonal across the first 3 rows, and a full row at the bottom
+        let mut display = Display::new(resolution);
+        for y in 0..3 {
+            display.pixels[y][y] = true;
+        }
+        display.pixels[resolution.height - 1].fill(true);
+        let mut state = Chip8State::new().with_display(display);
+
+        let opcode = 0x00C4;
+        DecodeTable::default()
+            .lookup(opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+
+        let mut expected = Display::new(resolution);
+        for y in 0..3 {
+            expected.pixels[y + scrolled][y] = true;
+        }
+        assert_eq!(state.display, expected);
+    }
+
+    // A vertical line near each edge, one of which gets scrolled off. Scrolls move 4 pixels in
+    // high resolution and 2 in low resolution.
+    #[test_case(0x00FB, SCREEN_RES, &[2, 62], &[4]; "right_low_res")]
+    #[test_case(0x00FC, SCREEN_RES, &[1, 61], &[59]; "left_low_res")]
+    #[test_case(0x00FB, HIGH_RES, &[2, 126], &[6]; "right_high_res")]
+    #[test_case(0x00FC, HIGH_RES, &[1, 125], &[121]; "left_high_res")]
+    fn test_scroll_horizontal(
+        opcode: u16,
+        resolution: Resolution,
+        lines: &[usize],
+        expected_lines: &[usize],
+    ) {
+        let with_lines = |lines: &[usize]| {
+            let mut display = Display::new(resolution);
+            for row in display.pixels.iter_mut() {
+                for x in lines {
+                    row[*x] = true;
+                }
+            }
+            display
+        };
+        let mut state = Chip8State::new().with_display(with_lines(lines));
+        DecodeTable::default()
+            .lookup(opcode)
+            .unwrap()
+            .execute(&mut state, OpCodeData::decode(opcode))
+            .unwrap();
+        assert_eq!(state.display, with_lines(expected_lines));
+    }
+
+    #[test]
+    fn test_resolution_switch() {
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[0x80], Address(0x300))
+            .with_register(Register(100), 0)
+            .with_register(Register(50), 1);
+        state.display.pixels[0][0] = true;
+
+        // Switching clears the screen, and draws can now reach past 64x32 without wrapping
+        HighRes
+            .execute(&mut state, OpCodeData::decode(0x00FF))
+            .unwrap();
+        assert_eq!(state.display.resolution(), HIGH_RES);
+        assert!(state.display.pixels.iter().flatten().all(|pixel| !pixel));
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD011))
+            .unwrap();
+        assert!(state.display.pixels[50][100]);
+
+        // Back in low resolution the same coordinates wrap around to (36, 18)
+        LowRes
+            .execute(&mut state, OpCodeData::decode(0x00FE))
+            .unwrap();
+        assert_eq!(state.display, Display::default());
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD011))
+            .unwrap();
+        assert!(state.display.pixels[18][36]);
+    }
+
+    #[test]
+    fn test_display_draw_wide_sprite() {
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[0xFF; 32], Address(0x300))
+            .with_register(Register(10), 0)
+            .with_register(Register(20), 1);
+        state.display.set_resolution(HIGH_RES);
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD010))
+            .unwrap();
+        assert_eq!(
+            state.display.bounding_box(),
+            Some((Coordinates { x: 10, y: 20 }, Coordinates { x: 25, y: 35 }))
+        );
+        let lit = state
+            .display
+            .pixels
+            .iter()
+            .flatten()
+            .filter(|pixel| **pixel);
+        assert_eq!(lit.count(), 16 * 16);
+        assert_eq!(state.gp_register(0xF).0, 0);
+
+        // Drawing it again a pixel across collides everywhere but the leftmost column
+        state = state
+            .with_register(Register(11), 0)
+            .with_diagnostics(Diagnostics {
+                report_collisions: true,
+                ..Diagnostics::default()
+            });
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD010))
+            .unwrap();
+        assert_eq!(state.gp_register(0xF).0, 1);
+        assert_eq!(state.last_collisions.len(), 15 * 16);
+    }
+
+    // The sprite's 3 rows start 2 rows above the bottom edge, so the last one gets clipped
+    #[test_case(30, false, 0x00; "clipped_ignored")]
+    #[test_case(30, true, 0x01; "clipped_collides")]
+    #[test_case(29, true, 0x00; "fits_on_screen")]
+    fn test_display_draw_clipped_rows_collide(y: u8, clipped_rows_collide: bool, vf_value: u8) {
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[0xFF, 0xFF, 0xFF], Address(0x300))
+            .with_register(Register(y), 0x1)
+            .with_register(Register(0xAA), 0xF)
+            .with_quirks(QuirkConfig {
+                clipped_rows_collide,
+                ..QuirkConfig::default()
+            });
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD013))
+            .unwrap();
+        assert_eq!(state.gp_register(0xF).0, vf_value);
+    }
+
+    #[test_case(true, 0x01; "wraps")]
+    #[test_case(false, 0x00; "clipped")]
+    fn test_display_draw_start_wraps(drw_start_wraps: bool, vf_value: u8) {
+        // Draws a pixel at (1, 1), then the same sprite again starting a whole screen further
+        // right, which only collides with it when the start position wraps
+        let mut state = Chip8State::new()
+            .with_index_register(Address(0x300))
+            .with_memory_set(&[0x80], Address(0x300))
+            .with_register(Register(1), 0x1)
+            .with_register(Register(65), 0x2)
+            .with_quirks(QuirkConfig {
+                drw_start_wraps,
+                ..QuirkConfig::default()
+            });
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD111))
+            .unwrap();
+        DisplayDraw
+            .execute(&mut state, OpCodeData::decode(0xD211))
+            .unwrap();
+        assert_eq!(state.gp_register(0xF).0, vf_value);
+        // Wrapping draws over the first pixel and turns it back off
+        assert_eq!(state.display.pixels[1][1], !drw_start_wraps);
+    }
+
+    #[test]
+    fn test_display_draw_report_collisions() {
+        let d_reader = DisplayDraw;
+        // The bottom row of the sprite (0xDF) lands on the top row of the box, at y=10
+        let mut state = get_draw_state().with_diagnostics(Diagnostics {
+            report_collisions: true,
+            ..Diagnostics::default()
+        });
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
+        assert_eq!(
+            state.last_collisions,
+            [56, 57, 59, 60, 61]
+                .into_iter()
+                .map(|x| Coordinates { x, y: 10 })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(state.gp_register(0xF).0, 0x01);
+    }
+
     #[test]
     fn test_display_draw_coordinate_wraps() {
         let d_reader = DisplayDraw;
@@ -1306,7 +2515,9 @@ mod test {
             .with_register(Register(136), 3); // y
 
         // Basic case, confirm it gets drawn
-        d_reader.execute(&mut state, OpCodeData::decode(0xD233));
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
 
         let after_screen = expect![[r#"
             .----------------------------------------------------------------.
@@ -1362,7 +2573,9 @@ mod test {
         //|██████████  |
         //[etc.]
         // truncation case
-        d_reader.execute(&mut state, OpCodeData::decode(0xD233));
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
 
         let after_screen = expect![[r#"
             .----------------------------------------------------------------.
@@ -1402,6 +2615,32 @@ mod test {
         after_screen.assert_eq(&state.display.to_string());
     }
 
+    #[test_case(0x300, &[]; "nonzero_sprite")]
+    #[test_case(0x400, &[Warning::BlankSprite { index: Address(0x400), rows: 3 }]; "blank_sprite")]
+    fn test_display_draw_warn_on_blank_sprite(index: u16, warnings: &[Warning]) {
+        let d_reader = DisplayDraw;
+        let mut state = get_draw_state()
+            .with_index_register(Address(index))
+            .with_diagnostics(Diagnostics {
+                warn_on_blank_sprite: true,
+                ..Diagnostics::default()
+            });
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
+        assert_eq!(state.warnings, warnings);
+    }
+
+    #[test]
+    fn test_display_draw_blank_sprite_diagnostic_disabled() {
+        let d_reader = DisplayDraw;
+        let mut state = get_draw_state().with_index_register(Address(0x400));
+        d_reader
+            .execute(&mut state, OpCodeData::decode(0xD233))
+            .unwrap();
+        assert!(state.warnings.is_empty());
+    }
+
     fn get_draw_state() -> Chip8State {
         let display = {
             let mut display = Display::default();
@@ -1,5 +1,15 @@
-use crate::emulator::{Address, Chip8State};
-use std::path::Path;
+use crate::{
+    emulator::{Address, Chip8State},
+    hash::StableHasher,
+    roms,
+};
+use std::{hash::Hasher, path::Path};
+
+/// Address programs get loaded at, and where execution starts
+pub const PROGRAM_START: Address = Address(0x200);
+
+/// Largest program that fits in memory, from [`PROGRAM_START`] up to the end at 0xFFF
+pub const MAX_PROGRAM_SIZE: usize = 0x1000 - PROGRAM_START.0 as usize;
 
 pub struct Program {
     data: Vec<u8>,
@@ -11,12 +21,13 @@ pub enum Error {
     ProgramTooLarge,
     #[error("could not read the ROM file: {0}")]
     CouldNotRead(#[source] std::io::Error),
+    #[error("there is no bundled ROM named '{0}'")]
+    UnknownBundledRom(String),
 }
 
 impl Program {
     pub fn new_from_data(data: &[u8]) -> Result<Program, Error> {
-        // We only accept programs of up to 8KB
-        if data.len() > 8 * 1024 {
+        if data.len() > MAX_PROGRAM_SIZE {
             return Err(Error::ProgramTooLarge);
         }
 
@@ -30,12 +41,91 @@ impl Program {
         Self::new_from_data(&data[..])
     }
 
+    /// Loads a program from a path, or from one of the bundled ROMs if the path is of the form
+    /// `@name`.
+    pub fn new_from_source<P: AsRef<Path>>(source: P) -> Result<Program, Error> {
+        let source = source.as_ref();
+        match source.to_str().and_then(|source| source.strip_prefix('@')) {
+            Some(name) => Self::new_from_bundled(name),
+            None => Self::new_from_file(source),
+        }
+    }
+
+    pub fn new_from_bundled(name: &str) -> Result<Program, Error> {
+        let rom =
+            roms::find_bundled_rom(name).ok_or_else(|| Error::UnknownBundledRom(name.into()))?;
+        Self::new_from_data(rom.data)
+    }
+
+    /// Stable 64-bit FNV-1a hash of the ROM contents, used to tell ROMs apart (e.g. when naming
+    /// save files). Unlike the std hashers, this is guaranteed to stay the same across builds.
+    pub fn hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        hasher.write(&self.data);
+        hasher.finish()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
     pub fn load(&self, state: &mut Chip8State) {
-        let start_idx = 0x200;
+        let start_idx = usize::from(PROGRAM_START);
         let end_idx = start_idx + self.data.len();
-        state.memory[start_idx..end_idx].copy_from_slice(&self.data[..]);
+        state
+            .memory_slice_mut(start_idx..end_idx)
+            .expect("programs are checked to fit in memory when created")
+            .copy_from_slice(&self.data[..]);
 
         // Set PC to program start
         state.pc = Address(start_idx as u16);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Error, Program, MAX_PROGRAM_SIZE};
+    use crate::emulator::Chip8State;
+
+    #[test]
+    fn test_bundled_rom_source() {
+        let program = Program::new_from_source("@hex_digits").unwrap();
+        assert_eq!(program.data, include_bytes!("../roms/hex_digits.ch8"));
+    }
+
+    #[test]
+    fn test_program_hash() {
+        // Known FNV-1a test vectors
+        assert_eq!(
+            Program::new_from_data(b"").unwrap().hash(),
+            0xcbf29ce484222325
+        );
+        assert_eq!(
+            Program::new_from_data(b"a").unwrap().hash(),
+            0xaf63dc4c8601ec8c
+        );
+    }
+
+    #[test]
+    fn test_program_size_limit() {
+        assert_eq!(MAX_PROGRAM_SIZE, 3584);
+        for size in [MAX_PROGRAM_SIZE - 1, MAX_PROGRAM_SIZE] {
+            let program = Program::new_from_data(&vec![0xAB; size]).unwrap();
+            let mut state = Chip8State::new();
+            program.load(&mut state);
+            assert_eq!(state.memory[0x200 + size - 1], 0xAB);
+        }
+        assert!(matches!(
+            Program::new_from_data(&vec![0xAB; MAX_PROGRAM_SIZE + 1]),
+            Err(Error::ProgramTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_unknown_bundled_rom_source() {
+        assert!(matches!(
+            Program::new_from_source("@not_a_rom"),
+            Err(Error::UnknownBundledRom(name)) if name == "not_a_rom"
+        ));
+    }
+}
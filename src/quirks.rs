@@ -0,0 +1,108 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Behaviours that differ between CHIP-8 interpreters. ROMs written against one interpreter can
+/// misbehave on another, so these let the emulator match whichever one a ROM expects. The
+/// defaults match what the emulator has always done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QuirkConfig {
+    /// Whether shifts (`8XY6`/`8XYE`) shift VX in place or copy VY into it first
+    pub shift_quirk: ShiftQuirk,
+    /// Which write wins when a shift (`8XY6`/`8XYE`) targets VF itself
+    pub shift_flag_order: ShiftFlagOrder,
+    /// Whether `FX55`/`FX65` leave I pointing past the registers they stored or loaded
+    pub memory_increment: MemoryIncrementQuirk,
+    /// Stop with [`crate::emulator::Fault::KeyOutOfRange`] when `EX9E`/`EXA1` check a key value
+    /// above 0xF, instead of only looking at its low nibble like real hardware does. Useful for
+    /// catching ROM bugs.
+    pub strict_key_values: bool,
+    /// Have `00E0` also zero VF, as some interpreters documented it doing
+    pub cls_resets_vf: bool,
+    /// Wrap memory accesses through I that run past the end of memory back round to the start,
+    /// instead of stopping with [`crate::emulator::Fault::IndexOutOfRange`]
+    pub index_wraps: bool,
+    /// Have `DXYN` set VF when any rows of the sprite get clipped off the bottom of the screen, as
+    /// if they had collided. Some interpreters (most notably SUPER-CHIP) count clipped rows this
+    /// way, and a few games rely on it to tell when something fell off the screen.
+    pub clipped_rows_collide: bool,
+    /// Whether a `DXYN` starting off the screen wraps round onto it, like almost every
+    /// interpreter does. When unset, the sprite is clipped away entirely and nothing is drawn.
+    pub drw_start_wraps: bool,
+    /// What to do when the program runs code below 0x200, where the font and (originally) the
+    /// interpreter live
+    pub reserved_execution: ReservedExecution,
+}
+
+impl Default for QuirkConfig {
+    fn default() -> QuirkConfig {
+        QuirkConfig {
+            shift_quirk: ShiftQuirk::default(),
+            shift_flag_order: ShiftFlagOrder::default(),
+            memory_increment: MemoryIncrementQuirk::default(),
+            strict_key_values: false,
+            cls_resets_vf: false,
+            index_wraps: false,
+            clipped_rows_collide: false,
+            drw_start_wraps: true,
+            reserved_execution: ReservedExecution::default(),
+        }
+    }
+}
+
+/// Where shifts (`8XY6`/`8XYE`) take the value they shift from. The COSMAC VIP shifted VY into VX,
+/// but CHIP-48 and SUPER-CHIP shift VX in place and ignore Y, and most ROMs since were written
+/// against the latter.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ShiftQuirk {
+    /// Shift VX in place
+    #[default]
+    InPlace,
+    /// Set VX to VY and then shift it, like the COSMAC VIP
+    CopyY,
+}
+
+/// What happens to I after `FX55`/`FX65`. The COSMAC VIP moved I along as it went, leaving it
+/// just past the last register stored or loaded, while CHIP-48 and SUPER-CHIP left it alone.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum MemoryIncrementQuirk {
+    /// Add X + 1 to I, like the COSMAC VIP
+    Increment,
+    /// Leave I unchanged
+    #[default]
+    Leave,
+}
+
+/// Shifts write the shifted value to VX and the shifted-out bit to VF. When X is F both writes
+/// land in the same register, so the order they happen in decides what's left in VF. This is
+/// independent of whether the shift reads its source from VX or VY.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ShiftFlagOrder {
+    /// The flag is written last, so VF ends up holding the shifted-out bit. This is what the
+    /// COSMAC VIP and most modern interpreters do
+    #[default]
+    FlagLast,
+    /// The result is written last, so VF ends up holding the shifted value
+    ResultLast,
+}
+
+/// Running code below 0x200 (in the font or where the original interpreter lived) is almost always
+/// a runaway jump, but some interpreter variants do put code there on purpose.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ReservedExecution {
+    /// Log a warning and carry on
+    #[default]
+    Warn,
+    /// Stop with [`crate::emulator::Fault::ReservedExecution`]
+    Error,
+    /// Carry on without a word, for variants that expect it
+    Allow,
+}
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+/// Write half of a double-buffered frame. The emulation loop fills the private back buffer and
+/// then publishes it with a single pointer swap, so the render thread never sees a frame that is
+/// only partially written.
+pub struct FrameWriter<T> {
+    back: T,
+    front: Arc<Mutex<Arc<T>>>,
+}
+
+/// Read half of a double-buffered frame, handed to the render thread.
+pub struct FrameReader<T> {
+    front: Arc<Mutex<Arc<T>>>,
+}
+
+/// Creates a connected writer/reader pair, with both buffers initialised to `T::default()`
+pub fn double_buffer<T: Clone + Default>() -> (FrameWriter<T>, FrameReader<T>) {
+    let front = Arc::new(Mutex::new(Arc::new(T::default())));
+    (
+        FrameWriter {
+            back: T::default(),
+            front: front.clone(),
+        },
+        FrameReader { front },
+    )
+}
+
+impl<T: Clone + Default> FrameWriter<T> {
+    /// Copies `frame` into the back buffer and swaps it to the front.
+    pub fn publish(&mut self, frame: &T) {
+        self.back.clone_from(frame);
+        self.swap();
+    }
+
+    /// Swaps the back buffer to the front. The previous front buffer is reclaimed as the new
+    /// back buffer unless a reader is still holding onto it.
+    fn swap(&mut self) {
+        let published = Arc::new(std::mem::take(&mut self.back));
+        let previous = std::mem::replace(&mut *self.front.lock().unwrap(), published);
+        self.back = Arc::try_unwrap(previous).unwrap_or_default();
+    }
+}
+
+impl<T> FrameReader<T> {
+    /// Returns the most recently published frame. The lock is only held long enough to clone
+    /// the pointer, so rendering can take as long as it needs.
+    pub fn latest(&self) -> Arc<T> {
+        self.front.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::double_buffer;
+    use crate::display::Display;
+    use std::thread;
+
+    #[test]
+    fn test_reader_never_sees_partial_frame() {
+        let (mut writer, reader) = double_buffer::<Display>();
+        let blank = Display::default();
+        let mut lit = Display::default();
+        lit.pixels
+            .iter_mut()
+            .flatten()
+            .for_each(|pixel| *pixel = true);
+        let published = [blank, lit];
+
+        let writer_jh = thread::spawn(move || {
+            for frame in 0..500 {
+                // Fill the back buffer one row at a time, yielding in between so the reader gets
+                // plenty of chances to catch a frame mid-write if the back buffer were visible
+                let lit = frame % 2 == 0;
+                for row in writer.back.pixels.iter_mut() {
+                    row.iter_mut().for_each(|pixel| *pixel = lit);
+                    thread::yield_now();
+                }
+                writer.swap();
+            }
+        });
+
+        while !writer_jh.is_finished() {
+            let frame = reader.latest();
+            assert!(
+                published.contains(&frame),
+                "observed a frame that was never published"
+            );
+        }
+        writer_jh.join().unwrap();
+    }
+}
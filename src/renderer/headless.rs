@@ -0,0 +1,164 @@
+use crate::{
+    display::Display,
+    emulator::{KeyInput, Register, RegisterSnapshot},
+    renderer::{
+        frame_buffer::{double_buffer, FrameReader, FrameWriter},
+        Command, Renderer,
+    },
+};
+use std::time::Duration;
+
+/// Decides which keys are held down, given how many frames have been drawn so far
+pub type KeyScript = Box<dyn Fn(usize) -> KeyInput + Send>;
+
+/// Renderer for running ROMs in automated tests. It keeps the latest frame around to be checked
+/// (through the [`FrameReader`] handed out by [`HeadlessRenderer::new_with_frame_budget`], since
+/// [`crate::emulator::EmulatedChip8::run`] takes the renderer itself), presses keys following a
+/// script, and terminates once it's been sent a set number of frames.
+pub struct HeadlessRenderer {
+    display: FrameWriter<Display>,
+    frames: usize,
+    frame_budget: Option<usize>,
+    keys: Option<KeyScript>,
+}
+
+impl HeadlessRenderer {
+    /// Creates a renderer that terminates after `frame_budget` frames, along with a reader for
+    /// the latest frame it was sent
+    pub fn new_with_frame_budget(frame_budget: usize) -> (HeadlessRenderer, FrameReader<Display>) {
+        let (display, display_reader) = double_buffer();
+        let renderer = HeadlessRenderer {
+            display,
+            frames: 0,
+            frame_budget: Some(frame_budget),
+            keys: None,
+        };
+        (renderer, display_reader)
+    }
+
+    /// Presses the keys `keys` returns for the current frame, instead of none at all
+    pub fn with_keys(mut self, keys: KeyScript) -> HeadlessRenderer {
+        self.keys = Some(keys);
+        self
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn new(_render_period: Duration) -> anyhow::Result<HeadlessRenderer> {
+        let (display, _) = double_buffer();
+        Ok(HeadlessRenderer {
+            display,
+            frames: 0,
+            frame_budget: None,
+            keys: None,
+        })
+    }
+
+    fn terminated(&self) -> bool {
+        self.frame_budget
+            .is_some_and(|frame_budget| self.frames >= frame_budget)
+    }
+
+    fn current_key_state(&self) -> KeyInput {
+        self.keys
+            .as_ref()
+            .map(|keys| keys(self.frames))
+            .unwrap_or_default()
+    }
+
+    fn next_command(&mut self) -> Option<Command> {
+        None
+    }
+
+    fn paused(&self) -> bool {
+        false
+    }
+
+    fn update_screen(&mut self, display: &Display) -> anyhow::Result<()> {
+        self.display.publish(display);
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn update_sound_timer(&mut self, _sound_timer: Register) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn set_buzzer(&mut self, _on: bool) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update_speed(&mut self, _speed: f64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update_registers(&mut self, _registers: &RegisterSnapshot) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HeadlessRenderer;
+    use crate::{
+        emulator::{EmulatedChip8, KeyInput},
+        font::Chip8Font,
+        program::Program,
+        roms::find_bundled_rom,
+    };
+    use expect_test::expect;
+
+    #[test]
+    fn test_keypad_rom_shows_pressed_key() {
+        let rom = find_bundled_rom("keypad").unwrap();
+        let mut chip = EmulatedChip8::new();
+        chip.write_font(&Chip8Font::new_from_default().unwrap());
+        chip.load_program(&Program::new_from_data(rom.data).unwrap());
+
+        // Tap key A for a few frames partway through
+        let (renderer, display) = HeadlessRenderer::new_with_frame_budget(20);
+        let renderer = renderer.with_keys(Box::new(|frame| {
+            let mut key_input = KeyInput::default();
+            key_input.key_state[0xA] = (5..10).contains(&frame);
+            key_input
+        }));
+        chip.run(renderer, 10_000.).unwrap();
+
+        expect![[r#"
+            .----------------------------------------------------------------.
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                            ████                                |
+            |                            █  █                                |
+            |                            ████                                |
+            |                            █  █                                |
+            |                            █  █                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            |                                                                |
+            .----------------------------------------------------------------."#]]
+        .assert_eq(&display.latest().to_string());
+    }
+}
@@ -0,0 +1,124 @@
+use crossterm::event::KeyCode;
+use std::{collections::HashMap, path::Path};
+
+/// Which keyboard key presses which keypad button. By default the 4x4 block of keys starting at
+/// `1` stands in for the keypad, in the same layout:
+///
+/// ```text
+/// 1 2 3 4        1 2 3 C
+/// q w e r        4 5 6 D
+/// a s d f   ->   7 8 9 E
+/// z x c v        A 0 B F
+/// ```
+///
+/// Keymap files are TOML tables from single characters to buttons, e.g. `a = 0x4`. A keymap file
+/// replaces the default mapping entirely, rather than adding to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap(HashMap<KeyCode, u8>);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not read the keymap file: {0}")]
+    CouldNotRead(#[source] std::io::Error),
+    #[error("invalid keymap file: {0}")]
+    InvalidKeyMap(#[source] toml::de::Error),
+    #[error("'{0}' is not a single character, so it can't be mapped")]
+    InvalidKey(String),
+    #[error("'{key}' is mapped to {button:#x}, but keypad buttons go from 0x0 to 0xF")]
+    ButtonOutOfRange { key: char, button: u8 },
+}
+
+impl KeyMap {
+    pub fn new_from_str(keymap: &str) -> Result<KeyMap, Error> {
+        let entries: HashMap<String, u8> = toml::from_str(keymap).map_err(Error::InvalidKeyMap)?;
+        entries
+            .into_iter()
+            .map(|(key, button)| {
+                let mut chars = key.chars();
+                let (Some(key), None) = (chars.next(), chars.next()) else {
+                    return Err(Error::InvalidKey(key));
+                };
+                if button > 0xF {
+                    return Err(Error::ButtonOutOfRange { key, button });
+                }
+                Ok((KeyCode::Char(key), button))
+            })
+            .collect::<Result<_, _>>()
+            .map(KeyMap)
+    }
+
+    pub fn new_from_file<P: AsRef<Path>>(path: P) -> Result<KeyMap, Error> {
+        let keymap = std::fs::read_to_string(path).map_err(Error::CouldNotRead)?;
+        Self::new_from_str(&keymap)
+    }
+
+    /// Returns the keypad button `key` is mapped to, if any
+    pub fn button(&self, key: KeyCode) -> Option<u8> {
+        self.0.get(&key).copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        let layout = [
+            ('1', 0x1),
+            ('2', 0x2),
+            ('3', 0x3),
+            ('4', 0xC),
+            ('q', 0x4),
+            ('w', 0x5),
+            ('e', 0x6),
+            ('r', 0xD),
+            ('a', 0x7),
+            ('s', 0x8),
+            ('d', 0x9),
+            ('f', 0xE),
+            ('z', 0xA),
+            ('x', 0x0),
+            ('c', 0xB),
+            ('v', 0xF),
+        ];
+        KeyMap(
+            layout
+                .into_iter()
+                .map(|(key, button)| (KeyCode::Char(key), button))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Error, KeyMap};
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn test_parse_keymap() {
+        let keymap = KeyMap::new_from_str(
+            r#"
+            "&" = 0x1
+            a = 0x4
+            z = 0x5
+            j = 0x5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(keymap.button(KeyCode::Char('j')), Some(0x5));
+        assert_eq!(keymap.button(KeyCode::Char('&')), Some(0x1));
+        // Keys not in the file aren't mapped, even if they are by default
+        assert_eq!(keymap.button(KeyCode::Char('w')), None);
+        assert_eq!(KeyMap::default().button(KeyCode::Char('w')), Some(0x5));
+
+        assert!(matches!(
+            KeyMap::new_from_str("space = 0x1"),
+            Err(Error::InvalidKey(key)) if key == "space"
+        ));
+        assert!(matches!(
+            KeyMap::new_from_str("j = 0x10"),
+            Err(Error::ButtonOutOfRange {
+                key: 'j',
+                button: 0x10
+            })
+        ));
+    }
+}
@@ -1,9 +1,38 @@
-use crate::{display::Display, emulator::KeyInput};
+use crate::{
+    display::Display,
+    emulator::{KeyInput, Register, RegisterSnapshot},
+};
 use std::time::Duration;
 
+mod frame_buffer;
+mod headless;
+mod keymap;
+mod null;
+mod palette;
+mod phosphor;
+mod recorder;
 mod tui;
 
-pub use tui::TuiRenderer;
+pub use frame_buffer::FrameReader;
+pub use headless::{HeadlessRenderer, KeyScript};
+pub use keymap::KeyMap;
+pub use null::NullRenderer;
+pub use palette::{plane_bits, Palette, PalettePreset};
+pub use phosphor::{intensity_color, Phosphor};
+pub use recorder::GifRecorder;
+pub use tui::{Aspect, GlyphSet, TuiOptions, TuiRenderer};
+
+/// Emulator controls requested through the renderer (e.g. through hotkeys), for the main loop to
+/// act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    SaveSlot(u8),
+    LoadSlot(u8),
+    /// Run one step faster, see [`crate::timing::adjust_speed`]
+    SpeedUp,
+    /// Run one step slower
+    SlowDown,
+}
 
 pub trait Renderer: Sized {
     /// Creates a new renderer of this type. No parameters are provided as this should be created
@@ -16,7 +45,30 @@ pub trait Renderer: Sized {
     /// Should return current state of keypad inputs
     fn current_key_state(&self) -> KeyInput;
 
+    /// Should return the next pending [`Command`], if any. Called repeatedly until it returns
+    /// `None`.
+    fn next_command(&mut self) -> Option<Command>;
+
+    /// Should return true while the user has paused emulation. The emulator stops stepping, so
+    /// time stands still for the program, but the screen keeps getting updated.
+    fn paused(&self) -> bool;
+
     /// Called every time there's an update to the screen. This being called doesn't necessarily
     /// mean that the data changed, just that we need to render to the screen.
     fn update_screen(&mut self, display: &Display) -> anyhow::Result<()>;
+
+    /// Called alongside [`Renderer::update_screen`] with the current value of the sound timer, so
+    /// renderers can signal when the buzzer would be sounding.
+    fn update_sound_timer(&mut self, sound_timer: Register) -> anyhow::Result<()>;
+
+    /// Called alongside [`Renderer::update_sound_timer`] to switch the buzzer on while the sound
+    /// timer is running, and off once it reaches zero.
+    fn set_buzzer(&mut self, on: bool) -> anyhow::Result<()>;
+
+    /// Called with the instruction rate, in Hz, when emulation starts and whenever it changes
+    fn update_speed(&mut self, speed: f64) -> anyhow::Result<()>;
+
+    /// Called alongside [`Renderer::update_screen`] with the current registers, for renderers
+    /// that show them
+    fn update_registers(&mut self, registers: &RegisterSnapshot) -> anyhow::Result<()>;
 }
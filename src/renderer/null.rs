@@ -0,0 +1,66 @@
+use crate::{
+    display::Display,
+    emulator::{KeyInput, Register, RegisterSnapshot},
+    renderer::{Command, Renderer},
+};
+use std::time::Duration;
+
+/// Renderer that doesn't draw anything and never has any keys pressed, for running the emulator
+/// headless (e.g. in tests and benchmarks).
+#[derive(Debug, Default)]
+pub struct NullRenderer {
+    frames: usize,
+    frame_limit: Option<usize>,
+}
+
+impl NullRenderer {
+    /// Makes the renderer report it's terminated once it's been sent `frame_limit` frames
+    pub fn with_frame_limit(mut self, frame_limit: usize) -> NullRenderer {
+        self.frame_limit = Some(frame_limit);
+        self
+    }
+}
+
+impl Renderer for NullRenderer {
+    fn new(_render_period: Duration) -> anyhow::Result<NullRenderer> {
+        Ok(NullRenderer::default())
+    }
+
+    fn terminated(&self) -> bool {
+        self.frame_limit
+            .is_some_and(|frame_limit| self.frames >= frame_limit)
+    }
+
+    fn current_key_state(&self) -> KeyInput {
+        KeyInput::default()
+    }
+
+    fn next_command(&mut self) -> Option<Command> {
+        None
+    }
+
+    fn paused(&self) -> bool {
+        false
+    }
+
+    fn update_screen(&mut self, _display: &Display) -> anyhow::Result<()> {
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn update_sound_timer(&mut self, _sound_timer: Register) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn set_buzzer(&mut self, _on: bool) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update_speed(&mut self, _speed: f64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update_registers(&mut self, _registers: &RegisterSnapshot) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
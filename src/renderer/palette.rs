@@ -0,0 +1,107 @@
+use ratatui::style::Color;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+/// Colours for each combination of lit bitplanes. Plain CHIP-8 only has one plane, so only
+/// entries `0b00` (off) and `0b01` (on) get used, but XO-CHIP's second plane makes all four
+/// reachable. Every renderer should pick its colours through [`Palette::color`] so they agree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Palette {
+    pub colors: [Color; 4],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            colors: [
+                Color::Black,
+                Color::White,
+                Color::LightRed,
+                Color::LightYellow,
+            ],
+        }
+    }
+}
+
+impl Palette {
+    /// Returns the colour for a pixel, given which of its planes are lit (see [`plane_bits`]).
+    pub fn color(&self, planes: u8) -> Color {
+        self.colors[usize::from(planes & 0b11)]
+    }
+
+    /// Same as [`Palette::color`], as RGB bytes for writing out images. Only works for RGB
+    /// colours, as what named colours look like is up to the terminal.
+    pub fn rgb(&self, planes: u8) -> Option<[u8; 3]> {
+        match self.color(planes) {
+            Color::Rgb(r, g, b) => Some([r, g, b]),
+            _ => None,
+        }
+    }
+}
+
+/// Named palettes, for picking colours from the command line or config file. They only change
+/// the off and first plane colours, like a monochrome monitor would.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum PalettePreset {
+    /// White on black
+    #[default]
+    White,
+    /// Amber on black, like old amber monochrome monitors
+    Amber,
+    /// Green on black, like old green phosphor monitors
+    Green,
+}
+
+impl From<PalettePreset> for Palette {
+    fn from(preset: PalettePreset) -> Palette {
+        let on = match preset {
+            PalettePreset::White => return Palette::default(),
+            PalettePreset::Amber => Color::Rgb(0xFF, 0xB0, 0x00),
+            PalettePreset::Green => Color::Rgb(0x33, 0xFF, 0x33),
+        };
+        let mut palette = Palette::default();
+        palette.colors[usize::from(plane_bits(true, false))] = on;
+        palette
+    }
+}
+
+/// Packs whether a pixel is lit on each plane into the index [`Palette::color`] expects, with the
+/// first plane as the low bit.
+pub fn plane_bits(first: bool, second: bool) -> u8 {
+    u8::from(first) | (u8::from(second) << 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{plane_bits, Palette, PalettePreset};
+    use clap::ValueEnum;
+    use ratatui::style::Color;
+    use test_case::test_case;
+
+    #[test_case(false, false, Color::Black;       "no_planes")]
+    #[test_case(true,  false, Color::White;       "first_plane")]
+    #[test_case(false, true,  Color::LightRed;    "second_plane")]
+    #[test_case(true,  true,  Color::LightYellow; "both_planes")]
+    fn test_plane_color(first: bool, second: bool, expected: Color) {
+        assert_eq!(
+            Palette::default().color(plane_bits(first, second)),
+            expected
+        );
+    }
+
+    #[test_case("white", Color::White;                  "white")]
+    #[test_case("amber", Color::Rgb(0xFF, 0xB0, 0x00); "amber")]
+    #[test_case("GREEN", Color::Rgb(0x33, 0xFF, 0x33); "green")]
+    fn test_parse_preset(name: &str, on: Color) {
+        let palette = Palette::from(PalettePreset::from_str(name, true).unwrap());
+        assert_eq!(palette.color(plane_bits(true, false)), on);
+        assert_eq!(palette.color(plane_bits(false, false)), Color::Black);
+    }
+
+    #[test]
+    fn test_unknown_preset() {
+        assert!(PalettePreset::from_str("purple", true).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    display::{Display, Resolution},
+    renderer::{plane_bits, Palette},
+};
+use ratatui::style::Color;
+
+/// How many draws a pixel takes to fade out completely once it's turned off
+pub const FADE_DRAWS: u8 = 4;
+
+/// Brightness of every pixel on screen, for drawing pixels that were just turned off as fading
+/// out rather than gone. Programs redraw sprites by erasing and drawing them again, so without
+/// this moving sprites flicker badly.
+///
+/// This is kept by the renderer across draws, independently of the [`Display`] it's fed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phosphor {
+    resolution: Resolution,
+    // Indexed as intensity[y][x], from 0 (off) to FADE_DRAWS (lit)
+    intensity: Vec<Vec<u8>>,
+}
+
+impl Phosphor {
+    pub fn new(resolution: Resolution) -> Phosphor {
+        Phosphor {
+            resolution,
+            intensity: vec![vec![0; resolution.width]; resolution.height],
+        }
+    }
+
+    /// Fades every pixel by a step, and relights the ones lit in `display`. Should be called once
+    /// per draw. Switching resolution starts over from a blank screen.
+    pub fn update(&mut self, display: &Display) {
+        if display.resolution() != self.resolution {
+            *self = Phosphor::new(display.resolution());
+        }
+        for (row, display_row) in self.intensity.iter_mut().zip(display.rows()) {
+            for (intensity, lit) in row.iter_mut().zip(display_row) {
+                *intensity = if *lit {
+                    FADE_DRAWS
+                } else {
+                    intensity.saturating_sub(1)
+                };
+            }
+        }
+    }
+
+    /// Brightness of each pixel, as rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.intensity.iter().map(|row| row.as_slice())
+    }
+}
+
+/// Colour for a pixel of the given intensity. Lit and fully faded pixels get the palette's
+/// colours; pixels in between get dimmer versions of the lit colour. Only RGB colours can be
+/// dimmed smoothly, so named ones fade through greys instead.
+pub fn intensity_color(palette: &Palette, intensity: u8) -> Color {
+    let on = palette.color(plane_bits(true, false));
+    match intensity {
+        0 => palette.color(plane_bits(false, false)),
+        FADE_DRAWS.. => on,
+        _ => match on {
+            Color::Rgb(r, g, b) => {
+                let dim = |channel: u8| {
+                    (u16::from(channel) * u16::from(intensity) / u16::from(FADE_DRAWS)) as u8
+                };
+                Color::Rgb(dim(r), dim(g), dim(b))
+            }
+            _ if intensity > FADE_DRAWS / 2 => Color::Gray,
+            _ => Color::DarkGray,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{intensity_color, Phosphor, FADE_DRAWS};
+    use crate::{
+        display::{Display, HIGH_RES},
+        renderer::{Palette, PalettePreset},
+    };
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_fade_out() {
+        let mut display = Display::default();
+        let mut phosphor = Phosphor::new(display.resolution());
+        display.pixels[2][3] = true;
+        phosphor.update(&display);
+        assert_eq!(phosphor.rows().nth(2).unwrap()[3], FADE_DRAWS);
+
+        display.clear();
+        for expected in (0..FADE_DRAWS).rev() {
+            phosphor.update(&display);
+            assert_eq!(phosphor.rows().nth(2).unwrap()[3], expected);
+        }
+
+        // Lighting it again brings it straight back to full brightness
+        display.pixels[2][3] = true;
+        phosphor.update(&display);
+        assert_eq!(phosphor.rows().nth(2).unwrap()[3], FADE_DRAWS);
+
+        // Switching resolution doesn't leave anything behind
+        phosphor.update(&Display::new(HIGH_RES));
+        assert_eq!(phosphor, Phosphor::new(HIGH_RES));
+    }
+
+    #[test]
+    fn test_intensity_color() {
+        let palette = Palette::default();
+        assert_eq!(intensity_color(&palette, 0), Color::Black);
+        assert_eq!(intensity_color(&palette, FADE_DRAWS), Color::White);
+        assert_eq!(intensity_color(&palette, 1), Color::DarkGray);
+
+        let amber = Palette::from(PalettePreset::Amber);
+        assert_eq!(
+            intensity_color(&amber, FADE_DRAWS / 2),
+            Color::Rgb(0x7F, 0x58, 0x00)
+        );
+    }
+}
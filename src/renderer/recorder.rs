@@ -0,0 +1,185 @@
+use crate::display::{Display, HIGH_RES};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// How many GIF pixels each high resolution pixel takes up. Low resolution pixels take twice as
+/// many, so both fill the same canvas.
+const SCALE: usize = 4;
+
+/// Shortest frame delay we write, in hundredths of a second. Browsers play anything shorter as
+/// if it were 1/10s, so frames drawn faster than this get skipped instead.
+const MIN_DELAY_CS: u64 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not create the recording file: {0}")]
+    CouldNotCreate(#[source] std::io::Error),
+    #[error("could not encode the recording: {0}")]
+    Encoding(#[from] gif::EncodingError),
+}
+
+/// Records the frames it's given as an animated GIF, timed after when they came in. Frames that
+/// don't change anything just make the previous one last longer. The GIF is finished off when
+/// the recorder is dropped.
+pub struct GifRecorder<W: Write> {
+    encoder: gif::Encoder<W>,
+    started: Instant,
+    /// Frame waiting for the next different one, so we know how long to show it for
+    pending: Option<Display>,
+    /// When the pending frame started showing, in hundredths of a second since we started
+    pending_since_cs: u64,
+}
+
+impl GifRecorder<BufWriter<File>> {
+    /// Starts recording to a new file at `path`, drawing lit pixels in `on` and the rest in `off`
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        on: [u8; 3],
+        off: [u8; 3],
+    ) -> Result<GifRecorder<BufWriter<File>>, Error> {
+        let file = File::create(path).map_err(Error::CouldNotCreate)?;
+        Self::new(BufWriter::new(file), on, off)
+    }
+}
+
+impl<W: Write> GifRecorder<W> {
+    pub fn new(writer: W, on: [u8; 3], off: [u8; 3]) -> Result<GifRecorder<W>, Error> {
+        let global_palette = [off, on].concat();
+        let mut encoder = gif::Encoder::new(
+            writer,
+            (HIGH_RES.width * SCALE) as u16,
+            (HIGH_RES.height * SCALE) as u16,
+            &global_palette,
+        )?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(GifRecorder {
+            encoder,
+            started: Instant::now(),
+            pending: None,
+            pending_since_cs: 0,
+        })
+    }
+
+    /// Adds the frame being shown right now
+    pub fn add_frame(&mut self, display: &Display) -> Result<(), Error> {
+        self.add_frame_at(display, self.started.elapsed())
+    }
+
+    /// Adds a frame shown at `at` since the recording started
+    fn add_frame_at(&mut self, display: &Display, at: Duration) -> Result<(), Error> {
+        let now_cs = centiseconds(at);
+        match &mut self.pending {
+            None => {
+                self.pending = Some(display.clone());
+                self.pending_since_cs = now_cs;
+            }
+            Some(pending) if pending == display => {}
+            // Too soon to show the pending frame for, so it never gets seen
+            Some(pending) if now_cs < self.pending_since_cs + MIN_DELAY_CS => {
+                *pending = display.clone();
+            }
+            Some(_) => {
+                self.write_pending(now_cs)?;
+                self.pending = Some(display.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes out the last frame and ends the GIF. Dropping the recorder does the same, but
+    /// ignores any errors.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        // The last frame gets shown for as long as it's been up so far
+        let now_cs = centiseconds(self.started.elapsed());
+        self.write_pending(now_cs.max(self.pending_since_cs + MIN_DELAY_CS))
+    }
+
+    /// Writes the pending frame, showing it until `until_cs`
+    fn write_pending(&mut self, until_cs: u64) -> Result<(), Error> {
+        let Some(display) = self.pending.take() else {
+            return Ok(());
+        };
+        let delay = (until_cs - self.pending_since_cs).min(u64::from(u16::MAX));
+        self.pending_since_cs += delay;
+
+        let scale = SCALE * HIGH_RES.width / display.resolution().width;
+        let width = display.resolution().width * scale;
+        let height = display.resolution().height * scale;
+        let mut buffer = Vec::with_capacity(width * height);
+        for row in display.rows() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|pixel| std::iter::repeat_n(u8::from(*pixel), scale))
+                .collect();
+            for _ in 0..scale {
+                buffer.extend_from_slice(&line);
+            }
+        }
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay: delay as u16,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        };
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for GifRecorder<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            log::error!("could not finish the recording: {e}");
+        }
+    }
+}
+
+fn centiseconds(duration: Duration) -> u64 {
+    (duration.as_millis() / 10) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::GifRecorder;
+    use crate::display::{Display, HIGH_RES};
+    use std::time::Duration;
+
+    #[test]
+    fn test_record_frames() {
+        let mut gif = Vec::new();
+        let mut recorder = GifRecorder::new(&mut gif, [0xFF; 3], [0x00; 3]).unwrap();
+        let mut display = Display::default();
+        let frame = |n: u64| Duration::from_micros(16_667 * n);
+        recorder.add_frame_at(&display, frame(0)).unwrap();
+        // Unchanged, so just extends the first frame
+        recorder.add_frame_at(&display, frame(1)).unwrap();
+        display.pixels[0][0] = true;
+        recorder.add_frame_at(&display, frame(2)).unwrap();
+        // Only shown for 1/100s, which is too short for a GIF frame, so it's replaced by the next
+        display.pixels[0][1] = true;
+        recorder.add_frame_at(&display, frame(3)).unwrap();
+        display.set_resolution(HIGH_RES);
+        recorder.add_frame_at(&display, frame(4)).unwrap();
+        recorder.finish().unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            // Both resolutions fill the whole canvas
+            assert_eq!((frame.width, frame.height), (512, 256));
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, [3, 2, 2]);
+    }
+}
@@ -1,22 +1,44 @@
-use crate::{display::Display, emulator::KeyInput, renderer::Renderer};
+use crate::{
+    audio::{Buzzer, DEFAULT_BUZZER_FREQUENCY},
+    display::{Display, Resolution, HIGH_RES, SCREEN_RES},
+    emulator::{KeyInput, Register, RegisterSnapshot},
+    renderer::{
+        frame_buffer::{double_buffer, FrameReader, FrameWriter},
+        intensity_color, plane_bits, Command, GifRecorder, KeyMap, Palette, Phosphor, Renderer,
+    },
+    save_state::SLOT_COUNT,
+};
 use anyhow::Context;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
-use log::info;
+use log::{error, info};
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Layout},
-    widgets::{Block, Borders, Paragraph},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Frame, Terminal,
 };
+#[cfg(feature = "serde")]
+use serde::Deserialize;
 use spin_sleep::LoopHelper;
 use std::{
-    io::Stdout,
+    fs::File,
+    io::{BufWriter, Stdout},
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
+        mpsc::{self, Receiver, Sender},
         Arc, Mutex,
     },
     thread::{self, JoinHandle},
@@ -30,15 +52,240 @@ pub struct TuiRenderer {
     render_jh: Option<JoinHandle<anyhow::Result<()>>>,
     event_jh: Option<JoinHandle<anyhow::Result<()>>>,
     key_state: Arc<Mutex<(KeyInput, [Instant; 0x10])>>,
-    display: Arc<Mutex<Display>>,
+    display: FrameWriter<Display>,
+    status: Arc<Status>,
+    toggles: Arc<Toggles>,
+    buzzer: Option<Buzzer>,
+    recorder: Option<GifRecorder<BufWriter<File>>>,
+    commands: Receiver<Command>,
     stop_state: Arc<AtomicBool>,
 }
 
+/// What the emulator reports back about itself, for the status displays
+#[derive(Debug, Default)]
+struct Status {
+    sound_timer: AtomicU8,
+    /// Instruction rate, rounded to the nearest Hz
+    speed: AtomicU32,
+    /// Only shown with [`TuiOptions::debug_panel`]
+    registers: Mutex<RegisterSnapshot>,
+}
+
+/// Settings toggled through hotkeys in the event loop
+#[derive(Debug, Default)]
+struct Toggles {
+    /// Draws a grid over the display, to help line up sprites
+    show_grid: AtomicBool,
+    /// Stops the emulator stepping, see [`Renderer::paused`]
+    paused: AtomicBool,
+    /// Set when a screenshot is asked for, until the render loop has saved it
+    screenshot: AtomicBool,
+    /// Set when asked to stop recording, see [`TuiOptions::record`]
+    stop_recording: AtomicBool,
+}
+
+/// Set of characters used to draw pixels onto the terminal
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[value(rename_all = "lower")]
+pub enum GlyphSet {
+    /// Unicode half blocks (`▀▄█`), packing two rows of pixels into each line
+    #[default]
+    HalfBlock,
+    /// Plain `#` characters, one line per row of pixels, for terminals without Unicode support
+    Ascii,
+    /// Unicode full blocks (`█`), one line per row of pixels, for fonts where half blocks don't
+    /// line up
+    FullBlock,
+}
+
+impl GlyphSet {
+    /// Picks the glyphs the current terminal can most likely display, based on the locale and
+    /// terminal type in the environment.
+    /// # Arguments
+    /// * `force_ascii` - Always use ASCII, regardless of what the environment says
+    pub fn detect(force_ascii: bool) -> GlyphSet {
+        // The first of these that is set decides the character encoding
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+        let term = std::env::var("TERM").ok();
+        Self::select(force_ascii, locale.as_deref(), term.as_deref())
+    }
+
+    fn select(force_ascii: bool, locale: Option<&str>, term: Option<&str>) -> GlyphSet {
+        let utf8_locale = locale
+            .map(|locale| {
+                let locale = locale.to_ascii_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            })
+            .unwrap_or(false);
+        let limited_term = matches!(term, Some("dumb") | Some("vt100") | Some("vt220"));
+
+        if force_ascii || !utf8_locale || limited_term {
+            GlyphSet::Ascii
+        } else {
+            GlyphSet::HalfBlock
+        }
+    }
+
+    /// Character drawn for lit pixels, with the glyph sets that draw one pixel per character
+    fn lit_pixel(self) -> char {
+        match self {
+            GlyphSet::FullBlock => '█',
+            GlyphSet::HalfBlock | GlyphSet::Ascii => '#',
+        }
+    }
+}
+
+/// How wide each pixel is drawn with [`GlyphSet::Ascii`] and [`GlyphSet::FullBlock`]. Terminal
+/// cells are roughly twice as tall
+/// as they are wide, so one character per pixel squishes the display horizontally. Half blocks
+/// don't need this, as they already fit two pixels in each cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Aspect {
+    /// One character per pixel
+    #[default]
+    Square,
+    /// Two characters per pixel, so pixels come out roughly square
+    Correct,
+}
+
+/// Options controlling how the [`TuiRenderer`] draws
+#[derive(Debug, Default, Clone)]
+pub struct TuiOptions {
+    pub glyphs: GlyphSet,
+    pub aspect: Aspect,
+    pub palette: Palette,
+    /// Fade pixels out over a few frames after they're turned off, see [`Phosphor`]
+    pub ghosting: bool,
+    /// Shows the registers, timers and stack depth next to the display
+    pub debug_panel: bool,
+    /// Records every frame to a GIF at this path, until Ctrl+R is pressed or the renderer is
+    /// dropped
+    pub record: Option<PathBuf>,
+    /// Pitch of the buzzer in Hz. Defaults to [`DEFAULT_BUZZER_FREQUENCY`]
+    pub buzzer_frequency: Option<f32>,
+    /// Keys standing in for the keypad. The emulator controls (Esc, Space, F1-F4, `+`, `=`, `-`,
+    /// `g`, `p` and Ctrl+R) take priority
+    pub keymap: KeyMap,
+}
+
 impl Renderer for TuiRenderer {
     fn new(render_period: Duration) -> anyhow::Result<TuiRenderer> {
+        Self::new_with_options(render_period, TuiOptions::default())
+    }
+
+    fn terminated(&self) -> bool {
+        join_handle_finished(&self.event_jh) || join_handle_finished(&self.render_jh)
+    }
+
+    fn current_key_state(&self) -> KeyInput {
+        self.key_state.lock().unwrap().0.clone()
+    }
+
+    fn next_command(&mut self) -> Option<Command> {
+        self.commands.try_recv().ok()
+    }
+
+    fn paused(&self) -> bool {
+        self.toggles.paused.load(Ordering::Relaxed)
+    }
+
+    fn update_screen(&mut self, display: &Display) -> anyhow::Result<()> {
+        self.display.publish(display);
+        if self.toggles.stop_recording.swap(false, Ordering::Relaxed) {
+            if let Some(recorder) = self.recorder.take() {
+                match recorder.finish() {
+                    Ok(()) => info!("Stopped recording"),
+                    Err(e) => error!("Could not finish the recording: {e}"),
+                }
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            // A broken recording shouldn't stop the game
+            if let Err(e) = recorder.add_frame(display) {
+                error!("Stopped recording: {e}");
+                self.recorder = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn update_sound_timer(&mut self, sound_timer: Register) -> anyhow::Result<()> {
+        self.status
+            .sound_timer
+            .store(sound_timer.0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn update_speed(&mut self, speed: f64) -> anyhow::Result<()> {
+        self.status
+            .speed
+            .store(speed.round() as u32, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn update_registers(&mut self, registers: &RegisterSnapshot) -> anyhow::Result<()> {
+        *self.status.registers.lock().unwrap() = *registers;
+        Ok(())
+    }
+
+    fn set_buzzer(&mut self, on: bool) -> anyhow::Result<()> {
+        if let Some(buzzer) = &self.buzzer {
+            buzzer.set_on(on);
+        }
+        Ok(())
+    }
+}
+
+fn join_handle_finished<T>(jh: &Option<JoinHandle<T>>) -> bool {
+    jh.as_ref().map(|jh| jh.is_finished()).unwrap_or(true)
+}
+
+impl TuiRenderer {
+    /// How long a key stays pressed after we see it, on terminals that don't report key releases
+    pub const KEY_PRESS_DURATION: Duration = Duration::from_millis(500);
+
+    pub fn new_with_options(
+        render_period: Duration,
+        options: TuiOptions,
+    ) -> anyhow::Result<TuiRenderer> {
+        // Before touching the terminal, so a bad path gets reported normally
+        let recorder = match &options.record {
+            Some(path) => Some(
+                GifRecorder::create(
+                    path,
+                    options
+                        .palette
+                        .rgb(plane_bits(true, false))
+                        .unwrap_or([0xFF; 3]),
+                    options
+                        .palette
+                        .rgb(plane_bits(false, false))
+                        .unwrap_or([0x00; 3]),
+                )
+                .with_context(|| format!("could not record to {}", path.display()))?,
+            ),
+            None => None,
+        };
+
         let mut stdout = std::io::stdout();
         enable_raw_mode().context("failed to enable raw mode")?;
         execute!(stdout, EnterAlternateScreen).context("unable to enter alternate screen")?;
+        // Terminals supporting the kitty keyboard protocol can tell us when keys get released
+        let key_releases = supports_keyboard_enhancement().unwrap_or(false);
+        if key_releases {
+            execute!(
+                stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )
+            .context("unable to enable key release events")?;
+        }
 
         // Setup panic handler to cleanup terminal
         let original_hook = std::panic::take_hook();
@@ -57,56 +304,69 @@ impl Renderer for TuiRenderer {
         let stop_state_clone = stop_state.clone();
         let stop_state_clone_2 = stop_state.clone();
 
-        let display: Arc<Mutex<Display>> = Arc::default();
-        let display_clone = display.clone();
+        let toggles: Arc<Toggles> = Arc::default();
+        let toggles_clone = toggles.clone();
+        let toggles_clone_2 = toggles.clone();
+
+        let (display, display_reader) = double_buffer();
+
+        let status: Arc<Status> = Arc::default();
+        let status_clone = status.clone();
 
         let key_state: Arc<Mutex<(KeyInput, [Instant; 0x10])>> =
             Arc::new(Mutex::new((KeyInput::default(), [Instant::now(); 0x10])));
         let key_state_clone = key_state.clone();
 
+        let (command_tx, commands) = mpsc::channel();
+        let keymap = options.keymap.clone();
+
+        // Carry on silently without audio, the status line still shows when the buzzer is on
+        let buzzer_frequency = options.buzzer_frequency.unwrap_or(DEFAULT_BUZZER_FREQUENCY);
+        let buzzer = Buzzer::new(buzzer_frequency)
+            .inspect_err(|e| info!("playing without sound: {e:#}"))
+            .ok();
+
         Ok(TuiRenderer {
             terminal,
             render_jh: Some(thread::spawn(move || {
                 Self::run_loop(
                     terminal_clone,
-                    display_clone,
+                    display_reader,
+                    status_clone,
                     render_period,
+                    options,
                     stop_state_clone,
+                    toggles_clone,
                 )
             })),
             event_jh: Some(thread::spawn(move || {
-                Self::event_loop(key_state_clone, stop_state_clone_2)
+                Self::event_loop(
+                    key_state_clone,
+                    command_tx,
+                    stop_state_clone_2,
+                    toggles_clone_2,
+                    keymap,
+                    key_releases,
+                )
             })),
             display,
+            status,
+            toggles,
+            buzzer,
+            recorder,
+            commands,
             stop_state,
             key_state,
         })
     }
 
-    fn terminated(&self) -> bool {
-        join_handle_finished(&self.event_jh) || join_handle_finished(&self.render_jh)
-    }
-
-    fn current_key_state(&self) -> KeyInput {
-        self.key_state.lock().unwrap().0.clone()
-    }
-
-    fn update_screen(&mut self, display: &Display) -> anyhow::Result<()> {
-        *self.display.lock().unwrap() = display.clone();
-        Ok(())
-    }
-}
-
-fn join_handle_finished<T>(jh: &Option<JoinHandle<T>>) -> bool {
-    jh.as_ref().map(|jh| jh.is_finished()).unwrap_or(true)
-}
-
-impl TuiRenderer {
-    const KEY_PRESS_DURATION: Duration = Duration::from_millis(500);
-
     fn event_loop(
         key_state: Arc<Mutex<(KeyInput, [Instant; 0x10])>>,
+        commands: Sender<Command>,
         stop_state: Arc<AtomicBool>,
+        toggles: Arc<Toggles>,
+        keymap: KeyMap,
+        key_releases: bool,
     ) -> anyhow::Result<()> {
         const POLL_TIMEOUT: Duration = Duration::from_millis(100);
 
@@ -115,8 +375,8 @@ impl TuiRenderer {
                 break;
             }
 
-            // Clear out key states over the duration, since we don't get key up events
-            {
+            // Without key up events, clear out key states once they've been held for a while
+            if !key_releases {
                 let mut lg = key_state.lock().unwrap();
                 for i in 0..lg.0.key_state.len() {
                     if lg.0.key_state[i] && lg.1[i].elapsed() > Self::KEY_PRESS_DURATION {
@@ -135,32 +395,57 @@ impl TuiRenderer {
                             stop_state.store(true, Ordering::Relaxed);
                             break;
                         }
-                        KeyCode::Char('1') => keypad_val = Some(0x1),
-                        KeyCode::Char('2') => keypad_val = Some(0x2),
-                        KeyCode::Char('3') => keypad_val = Some(0x3),
-                        KeyCode::Char('4') => keypad_val = Some(0xC),
-                        KeyCode::Char('q') => keypad_val = Some(0x4),
-                        KeyCode::Char('w') => keypad_val = Some(0x5),
-                        KeyCode::Char('e') => keypad_val = Some(0x6),
-                        KeyCode::Char('r') => keypad_val = Some(0xD),
-                        KeyCode::Char('a') => keypad_val = Some(0x7),
-                        KeyCode::Char('s') => keypad_val = Some(0x8),
-                        KeyCode::Char('d') => keypad_val = Some(0x9),
-                        KeyCode::Char('f') => keypad_val = Some(0xE),
-                        KeyCode::Char('z') => keypad_val = Some(0xA),
-                        KeyCode::Char('x') => keypad_val = Some(0x0),
-                        KeyCode::Char('c') => keypad_val = Some(0xB),
-                        KeyCode::Char('v') => keypad_val = Some(0xF),
-                        _ => {}
+                        // F1-F4 save to a slot, and with shift held load from it
+                        KeyCode::F(slot)
+                            if (1..=SLOT_COUNT).contains(&slot)
+                                && key.kind == KeyEventKind::Press =>
+                        {
+                            let command = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                                Command::LoadSlot(slot)
+                            } else {
+                                Command::SaveSlot(slot)
+                            };
+                            info!("Got request to {command:?}");
+                            // The receiver only goes away once we're shutting down
+                            let _ = commands.send(command);
+                        }
+                        KeyCode::Char(' ') if key.kind == KeyEventKind::Press => {
+                            let was_paused = toggles.paused.fetch_xor(true, Ordering::Relaxed);
+                            info!(
+                                "Got request to {}",
+                                if was_paused { "resume" } else { "pause" }
+                            );
+                        }
+                        KeyCode::Char(speed_key @ ('+' | '=' | '-'))
+                            if key.kind == KeyEventKind::Press =>
+                        {
+                            let command = if speed_key == '-' {
+                                Command::SlowDown
+                            } else {
+                                Command::SpeedUp
+                            };
+                            let _ = commands.send(command);
+                        }
+                        // Toggles a grid over the display, to help line up sprites
+                        KeyCode::Char('g') if key.kind == KeyEventKind::Press => {
+                            toggles.show_grid.fetch_xor(true, Ordering::Relaxed);
+                        }
+                        KeyCode::Char('r')
+                            if key.modifiers.contains(KeyModifiers::CONTROL)
+                                && key.kind == KeyEventKind::Press =>
+                        {
+                            info!("Got request to stop recording");
+                            toggles.stop_recording.store(true, Ordering::Relaxed);
+                        }
+                        KeyCode::Char('p') if key.kind == KeyEventKind::Press => {
+                            toggles.screenshot.store(true, Ordering::Relaxed);
+                        }
+                        code => keypad_val = keymap.button(code).map(usize::from),
                     }
 
                     if let Some(keypad_val) = keypad_val {
-                        if key.kind == KeyEventKind::Press {
-                            info!("Keypad button {:#x} pressed", keypad_val);
-                            let mut lg = key_state.lock().unwrap();
-                            lg.1[keypad_val] = Instant::now();
-                            lg.0.key_state[keypad_val] = true;
-                        }
+                        info!("Keypad button {:#x} {:?}", keypad_val, key.kind);
+                        apply_key_event(&mut key_state.lock().unwrap(), keypad_val, key.kind);
                     }
                 }
             }
@@ -171,11 +456,23 @@ impl TuiRenderer {
 
     fn run_loop(
         terminal: Arc<Mutex<CrossTerminal>>,
-        display: Arc<Mutex<Display>>,
+        display: FrameReader<Display>,
+        status: Arc<Status>,
         render_period: Duration,
+        options: TuiOptions,
         stop_state: Arc<AtomicBool>,
+        toggles: Arc<Toggles>,
     ) -> anyhow::Result<()> {
+        let mut grid = Display::grid(SCREEN_RES, 8);
+        let mut phosphor = options.ghosting.then(|| Phosphor::new(SCREEN_RES));
         let mut lh = LoopHelper::builder().build_with_target_rate(1. / render_period.as_secs_f32());
+        // Reused between frames so we're not allocating a new string for every draw. Sized for the
+        // largest resolution, so it doesn't have to grow if the program switches to it.
+        let mut display_str = String::with_capacity(display_str_capacity(
+            HIGH_RES,
+            options.glyphs,
+            options.aspect,
+        ));
         loop {
             lh.loop_start();
             // Check if the loop was stopped
@@ -183,39 +480,135 @@ impl TuiRenderer {
                 return Ok(());
             }
             {
-                let display = display.lock().unwrap();
+                let mut display = display.latest();
+                if toggles.screenshot.swap(false, Ordering::Relaxed) {
+                    match save_screenshot(&display, &options.palette) {
+                        Ok(path) => info!("Saved a screenshot to {}", path.display()),
+                        Err(e) => error!("Could not save a screenshot: {e:#}"),
+                    }
+                }
+                if toggles.show_grid.load(Ordering::Relaxed) {
+                    if grid.resolution() != display.resolution() {
+                        grid = Display::grid(display.resolution(), 8);
+                    }
+                    Arc::make_mut(&mut display).overlay(&grid);
+                }
+                if let Some(phosphor) = &mut phosphor {
+                    phosphor.update(&display);
+                }
+                let title = display_title(
+                    status.speed.load(Ordering::Relaxed),
+                    toggles.paused.load(Ordering::Relaxed),
+                );
                 let mut terminal = terminal.lock().unwrap();
-                terminal.draw(|frame| Self::draw(frame, &display))?
+                terminal.draw(|frame| {
+                    Self::draw(
+                        frame,
+                        &display,
+                        &mut display_str,
+                        phosphor.as_ref(),
+                        &title,
+                        &status,
+                        &options,
+                    )
+                })?
             };
             lh.loop_sleep();
         }
     }
 
-    fn draw(f: &mut Frame<'_>, display: &Display) {
-        let display_str = display_to_str(display);
-
+    fn draw(
+        f: &mut Frame<'_>,
+        display: &Display,
+        display_str: &mut String,
+        phosphor: Option<&Phosphor>,
+        title: &str,
+        status: &Status,
+        options: &TuiOptions,
+    ) {
         let size = f.size();
+        let panel_width = if options.debug_panel {
+            DEBUG_PANEL_WIDTH
+        } else {
+            0
+        };
+        // Room left for the display, next to the debug panel
+        let display_size = Rect {
+            width: size.width.saturating_sub(panel_width),
+            ..size
+        };
+        // ratatui resizes the terminal before every draw, so once it's been made big enough again
+        // we go straight back to drawing the display
+        let resolution = display.resolution();
+        let (min_width, min_height) = min_terminal_size(resolution, options.glyphs, options.aspect);
+        if !fits_terminal(display_size, resolution, options.glyphs, options.aspect) {
+            let message = Paragraph::new(format!(
+                "terminal too small (need {}x{min_height})",
+                min_width + panel_width
+            ))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+            let middle = Rect {
+                y: size.y + size.height / 2,
+                height: size.height - size.height / 2,
+                ..size
+            };
+            f.render_widget(message, middle);
+            return;
+        }
 
+        // The display takes as many lines as the glyph set needs, minus the blank line above it and
+        // the status line below
         let chunks = Layout::default()
             .constraints(
                 [
-                    Constraint::Min(1),
-                    Constraint::Ratio(2, 1),
+                    Constraint::Length(1),
+                    Constraint::Length(min_height - 2),
                     Constraint::Min(1),
                 ]
                 .as_ref(),
             )
             .split(size);
 
-        let canvas = Paragraph::new(display_str).block(
-            Block::default()
-                .title("Chip 8 Display")
-                .borders(Borders::ALL),
-        );
-        f.render_widget(canvas, chunks[1]);
+        // Each character cell can only have one foreground colour, so this only covers the first
+        // plane
+        let style = Style::default()
+            .fg(options.palette.color(plane_bits(true, false)))
+            .bg(options.palette.color(plane_bits(false, false)));
+        let text = match phosphor {
+            Some(phosphor) => {
+                phosphor_text(phosphor, options.glyphs, options.aspect, &options.palette)
+            }
+            None => {
+                write_display_str(display_str, display, options.glyphs, options.aspect);
+                Text::raw(display_str.as_str())
+            }
+        };
+        let canvas = Paragraph::new(text)
+            .style(style)
+            .block(Block::default().title(title).borders(Borders::ALL));
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(panel_width)].as_ref())
+            .split(chunks[1]);
+        f.render_widget(canvas, columns[0]);
+
+        if options.debug_panel {
+            let registers = *status.registers.lock().unwrap();
+            let panel = Paragraph::new(register_panel(&registers))
+                .block(Block::default().title("Registers").borders(Borders::ALL));
+            f.render_widget(panel, columns[1]);
+        }
+
+        let sound_timer = Register(status.sound_timer.load(Ordering::Relaxed));
+        let status_line = Paragraph::new(sound_indicator(sound_timer));
+        f.render_widget(status_line, chunks[2]);
     }
 
     fn reset_terminal() -> anyhow::Result<()> {
+        // Terminals without keyboard enhancements just ignore this
+        execute!(std::io::stdout(), PopKeyboardEnhancementFlags)
+            .context("unable to disable key release events")?;
         disable_raw_mode().context("failed to disable raw mode")?;
         execute!(std::io::stdout(), LeaveAlternateScreen)
             .context("unable to switch to main screen")?;
@@ -223,24 +616,230 @@ impl TuiRenderer {
     }
 }
 
-fn display_to_str(display: &Display) -> String {
-    let mut display_str = String::new();
-    // Every char will encode two vertical pixels, so we step by 2 in y
-    for y_idx in (0..display.pixels.len()).step_by(2) {
-        for x_idx in 0..display.pixels[y_idx].len() {
-            display_str += match (
-                display.pixels[y_idx][x_idx],
-                display.pixels[y_idx + 1][x_idx],
-            ) {
-                (false, false) => " ",
-                (true, false) => "▀",
-                (false, true) => "▄",
-                (true, true) => "█",
-            };
+/// How many image pixels each display pixel takes up in screenshots
+#[cfg(feature = "image")]
+const SCREENSHOT_SCALE: u32 = 8;
+
+/// Saves `display` to `screenshot-<unix time>.png` in the working directory, in the palette's
+/// colours where they're RGB and white on black otherwise. Returns the path it was saved to.
+#[cfg(feature = "image")]
+fn save_screenshot(display: &Display, palette: &Palette) -> anyhow::Result<std::path::PathBuf> {
+    let image = display.to_image(
+        SCREENSHOT_SCALE,
+        palette.rgb(plane_bits(true, false)).unwrap_or([0xFF; 3]),
+        palette.rgb(plane_bits(false, false)).unwrap_or([0x00; 3]),
+    );
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .context("system clock is set before 1970")?
+        .as_secs();
+    let path = std::path::PathBuf::from(format!("screenshot-{timestamp}.png"));
+    image
+        .save(&path)
+        .with_context(|| format!("could not write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(not(feature = "image"))]
+fn save_screenshot(_display: &Display, _palette: &Palette) -> anyhow::Result<std::path::PathBuf> {
+    anyhow::bail!("built without the image feature")
+}
+
+/// Updates which keypad buttons are held after a key event for `button`. Without release events
+/// (see [`TuiRenderer::KEY_PRESS_DURATION`]) only presses come through, and each one restarts the
+/// time the button stays held.
+fn apply_key_event(key_state: &mut (KeyInput, [Instant; 0x10]), button: usize, kind: KeyEventKind) {
+    match kind {
+        KeyEventKind::Press | KeyEventKind::Repeat => {
+            key_state.1[button] = Instant::now();
+            key_state.0.key_state[button] = true;
         }
-        display_str += "\n";
+        KeyEventKind::Release => key_state.0.key_state[button] = false,
+    }
+}
+
+/// Title of the box around the display, which also shows the speed and whether emulation is
+/// paused
+fn display_title(speed: u32, paused: bool) -> String {
+    if paused {
+        format!("Chip 8 Display ({speed}Hz, PAUSED)")
+    } else {
+        format!("Chip 8 Display ({speed}Hz)")
+    }
+}
+
+/// Text shown in the status line while the buzzer would be sounding. This gives some feedback
+/// even when there's no audio output.
+fn sound_indicator(sound_timer: Register) -> &'static str {
+    if sound_timer.0 > 0 {
+        "♪ BEEP"
+    } else {
+        ""
+    }
+}
+
+/// Columns taken up by the debug panel, including its border
+const DEBUG_PANEL_WIDTH: u16 = 16;
+
+/// Contents of the debug panel: PC, I, the timers, the stack depth, then V0-VF in two columns.
+/// Always fits in the display's height, which is at least 16 rows plus the border.
+fn register_panel(registers: &RegisterSnapshot) -> String {
+    let mut panel = format!(
+        "PC 0x{:03X}\nI  0x{:03X}\nDT {:02X}\nST {:02X}\nSP {}\n\n",
+        registers.pc.0,
+        registers.index_register.0,
+        registers.delay_timer,
+        registers.sound_timer,
+        registers.stack_depth,
+    );
+    for idx in 0..8 {
+        panel += &format!(
+            "V{:X} {:02X}  V{:X} {:02X}\n",
+            idx,
+            registers.registers[idx],
+            idx + 8,
+            registers.registers[idx + 8],
+        );
+    }
+    panel
+}
+
+/// Number of characters each pixel takes up horizontally
+fn pixel_width(glyphs: GlyphSet, aspect: Aspect) -> usize {
+    match (glyphs, aspect) {
+        (GlyphSet::Ascii | GlyphSet::FullBlock, Aspect::Correct) => 2,
+        _ => 1,
+    }
+}
+
+/// Smallest terminal (in columns and rows) a display at `resolution` fits in: a blank line, the
+/// display with its border, and the status line
+fn min_terminal_size(resolution: Resolution, glyphs: GlyphSet, aspect: Aspect) -> (u16, u16) {
+    let display_rows = match glyphs {
+        GlyphSet::HalfBlock => resolution.height.div_ceil(2),
+        GlyphSet::Ascii | GlyphSet::FullBlock => resolution.height,
+    };
+    let border = 2;
+    (
+        (resolution.width * pixel_width(glyphs, aspect) + border) as u16,
+        (1 + display_rows + border + 1) as u16,
+    )
+}
+
+/// Whether the display can be drawn in a terminal of the given size without getting clipped
+fn fits_terminal(size: Rect, resolution: Resolution, glyphs: GlyphSet, aspect: Aspect) -> bool {
+    let (min_width, min_height) = min_terminal_size(resolution, glyphs, aspect);
+    size.width >= min_width && size.height >= min_height
+}
+
+/// Number of bytes needed to hold a whole display at `resolution` as text, so the buffer never
+/// has to grow
+fn display_str_capacity(resolution: Resolution, glyphs: GlyphSet, aspect: Aspect) -> usize {
+    let Resolution { width, height } = resolution;
+    match glyphs {
+        // Half blocks take up to 3 bytes in UTF-8, and there's a newline after every pair of rows
+        GlyphSet::HalfBlock => height.div_ceil(2) * (width * 3 + 1),
+        GlyphSet::Ascii => height * (width * pixel_width(glyphs, aspect) + 1),
+        // Full blocks take 3 bytes too
+        GlyphSet::FullBlock => height * (width * pixel_width(glyphs, aspect) * 3 + 1),
+    }
+}
+
+/// Renders the display as text into `display_str`, replacing whatever was there before
+fn write_display_str(
+    display_str: &mut String,
+    display: &Display,
+    glyphs: GlyphSet,
+    aspect: Aspect,
+) {
+    display_str.clear();
+    match glyphs {
+        GlyphSet::HalfBlock => write_half_block_str(display_str, display),
+        GlyphSet::Ascii | GlyphSet::FullBlock => write_pixel_str(
+            display_str,
+            display,
+            pixel_width(glyphs, aspect),
+            glyphs.lit_pixel(),
+        ),
+    }
+}
+
+/// Renders the display with every cell coloured after how bright its pixels are. This can't reuse
+/// a single string like [`write_display_str`], as each cell needs its own colours.
+fn phosphor_text(
+    phosphor: &Phosphor,
+    glyphs: GlyphSet,
+    aspect: Aspect,
+    palette: &Palette,
+) -> Text<'static> {
+    let rows: Vec<&[u8]> = phosphor.rows().collect();
+    let lines: Vec<Line> = match glyphs {
+        // The top pixel takes the foreground colour and the bottom one the background, so both
+        // halves of the cell can fade separately
+        GlyphSet::HalfBlock => rows
+            .chunks(2)
+            .map(|pair| {
+                let bottom = pair.get(1);
+                pair[0]
+                    .iter()
+                    .enumerate()
+                    .map(|(x_idx, top)| {
+                        let bottom = bottom.map(|bottom| bottom[x_idx]).unwrap_or(0);
+                        let style = Style::default()
+                            .fg(intensity_color(palette, *top))
+                            .bg(intensity_color(palette, bottom));
+                        Span::styled("▀", style)
+                    })
+                    .collect()
+            })
+            .collect(),
+        GlyphSet::Ascii | GlyphSet::FullBlock => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|intensity| {
+                        let pixel = if *intensity > 0 {
+                            glyphs.lit_pixel()
+                        } else {
+                            ' '
+                        };
+                        Span::styled(
+                            pixel.to_string().repeat(pixel_width(glyphs, aspect)),
+                            Style::default().fg(intensity_color(palette, *intensity)),
+                        )
+                    })
+                    .collect()
+            })
+            .collect(),
+    };
+    Text::from(lines)
+}
+
+fn write_half_block_str(display_str: &mut String, display: &Display) {
+    // Every char will encode two vertical pixels, so we go through rows in pairs
+    for (top, bottom) in display.pair_rows() {
+        for (x_idx, top_pixel) in top.iter().enumerate() {
+            let bottom_pixel = bottom.map(|bottom| bottom[x_idx]).unwrap_or(false);
+            display_str.push(match (*top_pixel, bottom_pixel) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            });
+        }
+        display_str.push('\n');
+    }
+}
+
+fn write_pixel_str(display_str: &mut String, display: &Display, pixel_width: usize, lit: char) {
+    for row in display.rows() {
+        for pixel in row {
+            for _ in 0..pixel_width {
+                display_str.push(if *pixel { lit } else { ' ' });
+            }
+        }
+        display_str.push('\n');
     }
-    display_str
 }
 
 impl Drop for TuiRenderer {
@@ -263,3 +862,238 @@ impl Drop for TuiRenderer {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        apply_key_event, display_str_capacity, display_title, fits_terminal, phosphor_text,
+        register_panel, sound_indicator, write_display_str, Aspect, GlyphSet, DEBUG_PANEL_WIDTH,
+    };
+    use crate::{
+        display::{Display, Resolution, HIGH_RES, SCREEN_RES},
+        emulator::{Address, KeyInput, Register, RegisterSnapshot},
+        renderer::{Palette, Phosphor},
+    };
+    use crossterm::event::KeyEventKind;
+    use expect_test::expect;
+    use ratatui::{
+        layout::Rect,
+        style::{Color, Style},
+        text::Span,
+    };
+    use std::time::Instant;
+    use test_case::test_case;
+
+    #[test]
+    fn test_apply_key_event() {
+        let mut key_state = (KeyInput::default(), [Instant::now(); 0x10]);
+        apply_key_event(&mut key_state, 0x5, KeyEventKind::Press);
+        apply_key_event(&mut key_state, 0x6, KeyEventKind::Press);
+        apply_key_event(&mut key_state, 0x5, KeyEventKind::Repeat);
+        assert!(key_state.0.key_state[0x5] && key_state.0.key_state[0x6]);
+
+        apply_key_event(&mut key_state, 0x5, KeyEventKind::Release);
+        assert!(!key_state.0.key_state[0x5]);
+        assert!(key_state.0.key_state[0x6]);
+    }
+
+    #[test]
+    fn test_display_title() {
+        assert_eq!(display_title(700, false), "Chip 8 Display (700Hz)");
+        assert_eq!(display_title(875, true), "Chip 8 Display (875Hz, PAUSED)");
+    }
+
+    #[test_case(0x00, ""; "silent")]
+    #[test_case(0x01, "♪ BEEP"; "last_tick")]
+    #[test_case(0xFF, "♪ BEEP"; "max_timer")]
+    fn test_sound_indicator(sound_timer: u8, expected: &str) {
+        assert_eq!(sound_indicator(Register(sound_timer)), expected);
+    }
+
+    #[test_case(false, Some("en_US.UTF-8"), Some("xterm-256color"), GlyphSet::HalfBlock; "utf8_terminal")]
+    #[test_case(false, Some("C.utf8"), None, GlyphSet::HalfBlock; "utf8_no_term")]
+    #[test_case(true, Some("en_US.UTF-8"), Some("xterm-256color"), GlyphSet::Ascii; "forced_ascii")]
+    #[test_case(false, Some("C"), Some("xterm"), GlyphSet::Ascii; "non_utf8_locale")]
+    #[test_case(false, None, Some("xterm"), GlyphSet::Ascii; "no_locale")]
+    #[test_case(false, Some("en_US.UTF-8"), Some("vt100"), GlyphSet::Ascii; "limited_terminal")]
+    fn test_glyph_set_select(
+        force_ascii: bool,
+        locale: Option<&str>,
+        term: Option<&str>,
+        expected: GlyphSet,
+    ) {
+        assert_eq!(GlyphSet::select(force_ascii, locale, term), expected);
+    }
+
+    #[test_case(GlyphSet::HalfBlock, 66, 20, true; "half_block_exact")]
+    #[test_case(GlyphSet::HalfBlock, 65, 20, false; "half_block_narrow")]
+    #[test_case(GlyphSet::HalfBlock, 120, 19, false; "half_block_short")]
+    #[test_case(GlyphSet::Ascii, 80, 24, false; "ascii_short")]
+    #[test_case(GlyphSet::Ascii, 80, 36, true; "ascii_exact")]
+    #[test_case(GlyphSet::FullBlock, 66, 35, false; "full_block_short")]
+    #[test_case(GlyphSet::FullBlock, 66, 36, true; "full_block_exact")]
+    fn test_fits_terminal(glyphs: GlyphSet, width: u16, height: u16, fits: bool) {
+        assert_eq!(
+            fits_terminal(
+                Rect::new(0, 0, width, height),
+                SCREEN_RES,
+                glyphs,
+                Aspect::Square
+            ),
+            fits
+        );
+    }
+
+    #[test_case(GlyphSet::Ascii, 129, 36, false; "ascii_narrow")]
+    #[test_case(GlyphSet::Ascii, 130, 36, true; "ascii_exact")]
+    #[test_case(GlyphSet::HalfBlock, 66, 20, true; "half_block_unaffected")]
+    fn test_fits_terminal_corrected(glyphs: GlyphSet, width: u16, height: u16, fits: bool) {
+        assert_eq!(
+            fits_terminal(
+                Rect::new(0, 0, width, height),
+                SCREEN_RES,
+                glyphs,
+                Aspect::Correct
+            ),
+            fits
+        );
+    }
+
+    #[test_case(GlyphSet::HalfBlock, 130, 36, true; "half_block_exact")]
+    #[test_case(GlyphSet::HalfBlock, 66, 20, false; "half_block_low_res_size")]
+    #[test_case(GlyphSet::Ascii, 130, 68, true; "ascii_exact")]
+    #[test_case(GlyphSet::Ascii, 130, 67, false; "ascii_short")]
+    fn test_fits_terminal_high_res(glyphs: GlyphSet, width: u16, height: u16, fits: bool) {
+        assert_eq!(
+            fits_terminal(
+                Rect::new(0, 0, width, height),
+                HIGH_RES,
+                glyphs,
+                Aspect::Square
+            ),
+            fits
+        );
+    }
+
+    #[test]
+    fn test_phosphor_text() {
+        let mut display = Display::default();
+        let mut phosphor = Phosphor::new(display.resolution());
+        display.pixels[0][0] = true;
+        display.pixels[3][1] = true;
+        phosphor.update(&display);
+        display.pixels[0][0] = false;
+        phosphor.update(&display);
+
+        let palette = Palette::default();
+        let text = phosphor_text(&phosphor, GlyphSet::HalfBlock, Aspect::Square, &palette);
+        assert_eq!(text.lines.len(), 16);
+        let fading = Style::default().fg(Color::Gray).bg(Color::Black);
+        assert_eq!(text.lines[0].spans[0], Span::styled("▀", fading));
+        let lit_bottom = Style::default().fg(Color::Black).bg(Color::White);
+        assert_eq!(text.lines[1].spans[1], Span::styled("▀", lit_bottom));
+
+        let text = phosphor_text(&phosphor, GlyphSet::Ascii, Aspect::Correct, &palette);
+        assert_eq!(text.lines.len(), 32);
+        assert_eq!(text.lines[0].spans[0].content, "##");
+        assert_eq!(text.lines[0].spans[1].content, "  ");
+    }
+
+    #[test_case(GlyphSet::HalfBlock, &["", "  ▄"]; "half_block")]
+    #[test_case(GlyphSet::Ascii, &["", "", "", "  #"]; "ascii")]
+    #[test_case(GlyphSet::FullBlock, &["", "", "", "  █"]; "full_block")]
+    fn test_single_pixel(glyphs: GlyphSet, expected: &[&str]) {
+        let mut display = Display::default();
+        display.pixels[3][2] = true;
+        let mut display_str = String::new();
+        write_display_str(&mut display_str, &display, glyphs, Aspect::Square);
+        let lines: Vec<&str> = display_str.lines().map(str::trim_end).collect();
+        assert_eq!(lines[..expected.len()], *expected);
+        assert!(lines[expected.len()..].iter().all(|line| line.is_empty()));
+    }
+
+    #[test]
+    fn test_register_panel() {
+        let mut registers = [0; 16];
+        registers[0x3] = 0x2A;
+        registers[0xF] = 0x01;
+        let panel = register_panel(&RegisterSnapshot {
+            registers,
+            index_register: Address(0x2F0),
+            pc: Address(0x21C),
+            delay_timer: 0x3C,
+            sound_timer: 0,
+            stack_depth: 2,
+        });
+        expect![[r#"
+            PC 0x21C
+            I  0x2F0
+            DT 3C
+            ST 00
+            SP 2
+
+            V0 00  V8 00
+            V1 00  V9 00
+            V2 00  VA 00
+            V3 2A  VB 00
+            V4 00  VC 00
+            V5 00  VD 00
+            V6 00  VE 00
+            V7 00  VF 01
+        "#]]
+        .assert_eq(&panel);
+        // The widest line plus the border has to fit in the panel
+        assert!(panel
+            .lines()
+            .all(|line| line.len() + 2 <= usize::from(DEBUG_PANEL_WIDTH)));
+        assert_eq!(panel.lines().count(), 14);
+    }
+
+    #[test]
+    fn test_display_to_ascii_str() {
+        let mut display = Display::default();
+        display.pixels[1][2] = true;
+        let mut display_str = String::new();
+        write_display_str(&mut display_str, &display, GlyphSet::Ascii, Aspect::Square);
+        let lines: Vec<&str> = display_str.lines().collect();
+        assert_eq!(lines.len(), 32);
+        assert_eq!(lines[0].trim_end(), "");
+        assert_eq!(lines[1].trim_end(), "  #");
+    }
+
+    #[test]
+    fn test_display_to_corrected_ascii_str() {
+        let mut display = Display::default();
+        display.pixels[1][2] = true;
+        display.pixels[1][3] = true;
+        let mut display_str = String::new();
+        write_display_str(&mut display_str, &display, GlyphSet::Ascii, Aspect::Correct);
+        let lines: Vec<&str> = display_str.lines().collect();
+        assert_eq!(lines.len(), 32);
+        assert!(lines.iter().all(|line| line.len() == 128));
+        assert_eq!(lines[1].trim_end(), "    ####");
+    }
+
+    #[test_case(SCREEN_RES, GlyphSet::HalfBlock, Aspect::Square; "half_block")]
+    #[test_case(SCREEN_RES, GlyphSet::Ascii, Aspect::Square; "ascii")]
+    #[test_case(SCREEN_RES, GlyphSet::Ascii, Aspect::Correct; "ascii_corrected")]
+    #[test_case(SCREEN_RES, GlyphSet::FullBlock, Aspect::Correct; "full_block_corrected")]
+    #[test_case(HIGH_RES, GlyphSet::HalfBlock, Aspect::Square; "half_block_high_res")]
+    #[test_case(HIGH_RES, GlyphSet::Ascii, Aspect::Correct; "ascii_corrected_high_res")]
+    fn test_display_str_buffer_reused(resolution: Resolution, glyphs: GlyphSet, aspect: Aspect) {
+        let capacity = display_str_capacity(resolution, glyphs, aspect);
+        let mut display_str = String::with_capacity(capacity);
+        let mut display = Display::new(resolution);
+        // Fully lit is the longest the text can get
+        for lit in [false, true, false] {
+            for pixel in display.pixels.iter_mut().flatten() {
+                *pixel = lit;
+            }
+            write_display_str(&mut display_str, &display, glyphs, aspect);
+            assert_eq!(display_str.capacity(), capacity);
+            assert!(display_str
+                .lines()
+                .all(|line| line.chars().all(|c| (c != ' ') == lit)));
+        }
+    }
+}
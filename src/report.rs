@@ -0,0 +1,110 @@
+use crate::{
+    coverage::Coverage,
+    emulator::{Chip8State, EmulatedChip8},
+};
+use std::fmt;
+
+/// Everything worth attaching to a bug report about a run, as plain text: the final registers,
+/// stack, timers and display, how many instructions ran and of which kinds, and any diagnostic
+/// warnings raised along the way. The instruction counts are only there if coverage was on.
+pub struct RunReport<'a> {
+    state: &'a Chip8State,
+    coverage: Option<&'a Coverage>,
+}
+
+impl<'a> RunReport<'a> {
+    pub fn new(chip: &'a EmulatedChip8) -> RunReport<'a> {
+        RunReport {
+            state: chip.get_state(),
+            coverage: chip.coverage(),
+        }
+    }
+}
+
+impl fmt::Display for RunReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state;
+        writeln!(f, "== Registers ==")?;
+        for (idx, register) in state.gp_registers.iter().enumerate() {
+            let separator = if idx % 8 == 7 { "\n" } else { " " };
+            write!(f, "V{idx:X}={register}{separator}")?;
+        }
+        writeln!(f, "PC={} I={}", state.pc, state.index_register)?;
+
+        writeln!(f, "\n== Timers ==")?;
+        writeln!(f, "DT={} ST={}", state.delay_timer, state.sound_timer)?;
+
+        writeln!(f, "\n== Stack ==")?;
+        writeln!(f, "{} entries: {}", state.stack.len(), state.stack)?;
+
+        writeln!(f, "\n== Display ==")?;
+        writeln!(f, "{}", state.display)?;
+
+        writeln!(f, "\n== Instructions ==")?;
+        match self.coverage {
+            Some(coverage) => {
+                writeln!(f, "{} executed", coverage.instruction_count())?;
+                for (opcode, count) in coverage.opcodes() {
+                    writeln!(f, "  {count:>10}  {opcode}")?;
+                }
+            }
+            None => writeln!(f, "not recorded (coverage was off)")?,
+        }
+
+        writeln!(f, "\n== Warnings ==")?;
+        if state.warnings.is_empty() {
+            writeln!(f, "none")?;
+        }
+        for warning in &state.warnings {
+            writeln!(f, "{warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RunReport;
+    use crate::{
+        diagnostics::Diagnostics,
+        emulator::{EmulatedChip8, KeyInput},
+        program::Program,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_report_sections() {
+        // LD I, 0x20A; CALL 0x206; (unused) 0x0000; DRW V0, V0, 1; JP 0x206. I points just past
+        // the end of the program, so the sprite is blank
+        let program =
+            Program::new_from_data(&[0xA2, 0x0A, 0x22, 0x06, 0x00, 0x00, 0xD0, 0x01, 0x12, 0x06])
+                .unwrap();
+        let mut chip = EmulatedChip8::new()
+            .with_coverage(true)
+            .with_diagnostics(Diagnostics {
+                warn_on_blank_sprite: true,
+                ..Diagnostics::default()
+            });
+        chip.load_program(&program);
+        for _ in 0..10 {
+            chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+        }
+
+        let report = RunReport::new(&chip).to_string();
+        for section in [
+            "== Registers ==",
+            "== Timers ==",
+            "== Stack ==",
+            "== Display ==",
+            "== Instructions ==",
+            "== Warnings ==",
+        ] {
+            assert!(report.contains(section), "missing {section} in:\n{report}");
+        }
+        assert!(report.contains("PC=0x0206 I=0x020a"));
+        assert!(report.contains("1 entries: [0x0204]"));
+        assert!(report.contains("10 executed"));
+        assert!(report.contains("         4  DisplayDraw"));
+        assert!(report.contains("drew a blank 1-row sprite from 0x020a"));
+    }
+}
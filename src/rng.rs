@@ -0,0 +1,58 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Small seedable random number generator behind `CXNN` (SplitMix64). It's kept as part of the
+/// machine state so that save states and seeded runs carry on exactly where they left off.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Chip8Rng {
+    state: u64,
+}
+
+impl Chip8Rng {
+    pub fn from_seed(seed: u64) -> Chip8Rng {
+        Chip8Rng { state: seed }
+    }
+
+    /// Seeds the generator from the system's random source
+    pub fn from_entropy() -> Chip8Rng {
+        Self::from_seed(rand::random())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u8(&mut self) -> u8 {
+        // The top bits are the best mixed
+        (self.next_u64() >> 56) as u8
+    }
+}
+
+impl Default for Chip8Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Chip8Rng;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut rng = Chip8Rng::from_seed(42);
+        let mut other_rng = Chip8Rng::from_seed(42);
+        let sequence: Vec<u8> = (0..16).map(|_| rng.next_u8()).collect();
+        let other_sequence: Vec<u8> = (0..16).map(|_| other_rng.next_u8()).collect();
+        assert_eq!(sequence, other_sequence);
+        assert_ne!(
+            Chip8Rng::from_seed(43).next_u64(),
+            Chip8Rng::from_seed(42).next_u64()
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+/// Number of quick save slots available through the renderer hotkeys
+pub const SLOT_COUNT: u8 = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("no program has been loaded, so there's nothing to save or load a slot for")]
+    NoProgramLoaded,
+    #[error("save slot {0} does not exist, there are only {SLOT_COUNT}")]
+    InvalidSlot(u8),
+    #[error("could not access save file {0:?}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[cfg(feature = "serde")]
+    #[error("save state data is invalid: {0}")]
+    InvalidData(#[source] bincode::Error),
+    #[cfg(not(feature = "serde"))]
+    #[error("built without the serde feature, so there are no save states")]
+    Unsupported,
+}
+
+/// Path of the file backing a save slot. Files are keyed on the ROM hash so that slots from
+/// different games never clash.
+/// # Arguments
+/// * `dir` - Directory save files are kept in
+/// * `rom_hash` - Hash of the loaded ROM (see [`crate::program::Program::hash`])
+/// * `slot` - Slot number, starting at 1
+pub fn slot_path<P: AsRef<Path>>(dir: P, rom_hash: u64, slot: u8) -> PathBuf {
+    dir.as_ref()
+        .join(format!("{rom_hash:016x}.slot{slot}.state"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::slot_path;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_slot_path() {
+        assert_eq!(
+            slot_path("saves", 0xaf63dc4c8601ec8c, 3),
+            PathBuf::from("saves/af63dc4c8601ec8c.slot3.state")
+        );
+        assert_eq!(
+            slot_path("", 0x1f, 1),
+            PathBuf::from("000000000000001f.slot1.state")
+        );
+    }
+}
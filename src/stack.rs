@@ -0,0 +1,187 @@
+use crate::emulator::Address;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Number of return addresses the stack can hold. The COSMAC VIP had room for 12, but most later
+/// interpreters went with 16, which is what programs tend to assume.
+pub const STACK_DEPTH: usize = 16;
+
+/// The call stack, as a fixed array of return addresses and a stack pointer, like the original
+/// hardware had. Unlike a growable collection, pushing onto a full stack fails instead of
+/// allocating more room. Saves serialise it as just the live entries, and only those count when
+/// comparing stacks.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "Vec<Address>", try_from = "Vec<Address>")
+)]
+pub struct Stack {
+    entries: [Address; STACK_DEPTH],
+    /// Points one past the top of the stack, so it's also the number of entries
+    pointer: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum Error {
+    #[error("the stack is full, it can only hold {STACK_DEPTH} return addresses")]
+    Overflow,
+    #[error("the stack is empty")]
+    Underflow,
+}
+
+impl Stack {
+    pub fn new() -> Stack {
+        Stack {
+            entries: [Address(0); STACK_DEPTH],
+            pointer: 0,
+        }
+    }
+
+    pub fn push(&mut self, address: Address) -> Result<(), Error> {
+        let entry = self.entries.get_mut(self.pointer).ok_or(Error::Overflow)?;
+        *entry = address;
+        self.pointer += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Address, Error> {
+        self.pointer = self.pointer.checked_sub(1).ok_or(Error::Underflow)?;
+        Ok(self.entries[self.pointer])
+    }
+
+    pub fn len(&self) -> usize {
+        self.pointer
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pointer == 0
+    }
+
+    /// Iterates over the entries from the bottom of the stack to the top
+    pub fn iter(&self) -> impl Iterator<Item = &Address> {
+        self.entries[..self.pointer].iter()
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Stack {
+        Stack::new()
+    }
+}
+
+impl PartialEq for Stack {
+    fn eq(&self, other: &Stack) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Stack {}
+
+impl Hash for Stack {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries[..self.pointer].hash(state);
+    }
+}
+
+impl From<Stack> for Vec<Address> {
+    fn from(stack: Stack) -> Vec<Address> {
+        stack.iter().copied().collect()
+    }
+}
+
+impl TryFrom<Vec<Address>> for Stack {
+    type Error = Error;
+
+    fn try_from(addresses: Vec<Address>) -> Result<Stack, Error> {
+        let mut stack = Stack::new();
+        for address in addresses {
+            stack.push(address)?;
+        }
+        Ok(stack)
+    }
+}
+
+/// Builds a stack from the bottom up. Panics if there are more than [`STACK_DEPTH`] addresses.
+impl FromIterator<Address> for Stack {
+    fn from_iter<I: IntoIterator<Item = Address>>(addresses: I) -> Stack {
+        Stack::try_from(addresses.into_iter().collect::<Vec<_>>())
+            .expect("too many addresses for the stack")
+    }
+}
+
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (idx, address) in self.iter().enumerate() {
+            let space = if idx == self.len() - 1 { "" } else { " " };
+            write!(f, "{address}{space}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Error, Stack, STACK_DEPTH};
+    use crate::emulator::Address;
+
+    fn full_stack() -> Stack {
+        (0..STACK_DEPTH as u16)
+            .map(|idx| Address(0x200 + idx * 2))
+            .collect()
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut stack = Stack::new();
+        stack.push(Address(0x200)).unwrap();
+        stack.push(Address(0x300)).unwrap();
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.pop(), Ok(Address(0x300)));
+        assert_eq!(stack.pop(), Ok(Address(0x200)));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_push_full() {
+        let mut stack = full_stack();
+        assert_eq!(stack.len(), STACK_DEPTH);
+        assert_eq!(stack.push(Address(0x400)), Err(Error::Overflow));
+        // The failed push leaves the stack as it was
+        assert_eq!(stack, full_stack());
+        assert_eq!(stack.pop(), Ok(Address(0x21E)));
+        stack.push(Address(0x400)).unwrap();
+        assert_eq!(stack.pop(), Ok(Address(0x400)));
+    }
+
+    #[test]
+    fn test_pop_empty() {
+        let mut stack = Stack::new();
+        assert_eq!(stack.pop(), Err(Error::Underflow));
+        stack.push(Address(0x200)).unwrap();
+        stack.pop().unwrap();
+        assert_eq!(stack.pop(), Err(Error::Underflow));
+        assert_eq!(stack, Stack::new());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialise_live_entries() {
+        let stack: Stack = [Address(0x200), Address(0x300)].into_iter().collect();
+        let bytes = bincode::serialize(&stack).unwrap();
+        assert_eq!(
+            bytes,
+            bincode::serialize(&vec![Address(0x200), Address(0x300)]).unwrap()
+        );
+        assert_eq!(bincode::deserialize::<Stack>(&bytes).unwrap(), stack);
+
+        let too_deep = vec![Address(0x200); STACK_DEPTH + 1];
+        let bytes = bincode::serialize(&too_deep).unwrap();
+        assert!(bincode::deserialize::<Stack>(&bytes).is_err());
+    }
+}
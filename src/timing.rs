@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+/// How often the display gets presented, at 60Hz
+pub const FRAME_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Longest the main loop goes without yielding to other threads. At high speeds the loop barely
+/// sleeps between instructions, which can keep the render and event threads waiting on locks for
+/// long enough to make input feel unresponsive.
+pub const YIELD_PERIOD: Duration = Duration::from_millis(2);
+
+/// Slowest and fastest instruction rates, in Hz, the speed can be adjusted to at runtime
+pub const SPEED_LIMITS: (f64, f64) = (1., 20_000.);
+
+/// How much each speed adjustment speeds up or slows down by
+const SPEED_STEP: f64 = 1.25;
+
+/// Returns the instruction rate one step faster or slower than `speed`, within [`SPEED_LIMITS`]
+pub fn adjust_speed(speed: f64, faster: bool) -> f64 {
+    let speed = if faster {
+        speed * SPEED_STEP
+    } else {
+        speed / SPEED_STEP
+    };
+    speed.clamp(SPEED_LIMITS.0, SPEED_LIMITS.1)
+}
+
+/// Keeps track of when frames are due, given how much time passes between calls. Time left over
+/// after each frame carries on towards the next one, so frames come at a steady cadence on
+/// average even when the caller's own loop doesn't line up with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramePacer {
+    period: Duration,
+    since_last_frame: Duration,
+}
+
+impl FramePacer {
+    pub fn new(period: Duration) -> FramePacer {
+        FramePacer {
+            period,
+            since_last_frame: Duration::ZERO,
+        }
+    }
+
+    /// Moves the pacer forward by `elapsed`, and returns how many frames became due
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.since_last_frame += elapsed;
+        let mut frames = 0;
+        while self.since_last_frame >= self.period {
+            self.since_last_frame -= self.period;
+            frames += 1;
+        }
+        frames
+    }
+
+    /// Moves the pacer forward by `elapsed`, and returns whether a frame should be presented.
+    /// When several frames became due at once (e.g. because the caller stalled) only one gets
+    /// presented, as there's no point drawing the same thing repeatedly.
+    pub fn present(&mut self, elapsed: Duration) -> bool {
+        self.advance(elapsed) > 0
+    }
+}
+
+impl Default for FramePacer {
+    /// A pacer presenting at the 60Hz display rate
+    fn default() -> FramePacer {
+        FramePacer::new(FRAME_PERIOD)
+    }
+}
+
+/// Eases the instruction rate towards a target instead of jumping to it straight away, so speed
+/// changes don't cause a sudden burst (or stall) of instructions and timer updates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateRamp {
+    current: f64,
+    target: f64,
+    ramp_time: Duration,
+}
+
+impl RateRamp {
+    /// Once we're this close (in Hz) to the target we just snap to it
+    const SNAP_DISTANCE: f64 = 0.5;
+
+    /// Creates a ramp already settled at `rate`.
+    /// # Arguments
+    /// * `rate` - The starting (and target) rate, in Hz
+    /// * `ramp_time` - Time constant of the easing. After this long we've covered ~63% of the
+    ///   distance to the target. A zero duration disables ramping entirely.
+    pub fn new(rate: f64, ramp_time: Duration) -> RateRamp {
+        RateRamp {
+            current: rate,
+            target: rate,
+            ramp_time,
+        }
+    }
+
+    /// Starts easing towards `target` from wherever the current rate is
+    pub fn set_target(&mut self, target: f64) {
+        self.target = target;
+    }
+
+    /// The rate we're easing towards, in Hz
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    /// The rate we've eased to so far, in Hz
+    pub fn current(&self) -> f64 {
+        self.current
+    }
+
+    pub fn settled(&self) -> bool {
+        self.current == self.target
+    }
+
+    /// Moves the current rate towards the target, given how much time has passed since the last
+    /// call, and returns the new current rate.
+    pub fn advance(&mut self, elapsed: Duration) -> f64 {
+        if self.ramp_time.is_zero() {
+            self.current = self.target;
+        } else {
+            // Exponential easing, so the result doesn't depend on how often we get called
+            let progress = 1. - (-elapsed.as_secs_f64() / self.ramp_time.as_secs_f64()).exp();
+            self.current += (self.target - self.current) * progress;
+        }
+
+        if (self.target - self.current).abs() < Self::SNAP_DISTANCE {
+            self.current = self.target;
+        }
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{adjust_speed, FramePacer, RateRamp, FRAME_PERIOD, YIELD_PERIOD};
+    use std::time::Duration;
+    use test_case::test_case;
+
+    #[test]
+    fn test_frame_pacer_cadence() {
+        let mut pacer = FramePacer::new(Duration::from_millis(10));
+        let presented: Vec<bool> = [4, 4, 4, 4, 4, 25, 1, 9, 0, 10]
+            .into_iter()
+            .map(|ms| pacer.present(Duration::from_millis(ms)))
+            .collect();
+        // 12ms in we present, with the 2ms left over counting towards the next frame
+        assert_eq!(
+            presented,
+            [false, false, true, false, true, true, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_frame_pacer_counts_missed_frames() {
+        let mut pacer = FramePacer::default();
+        assert_eq!(pacer.advance(FRAME_PERIOD * 3 + FRAME_PERIOD / 2), 3);
+        assert_eq!(pacer.advance(FRAME_PERIOD / 2), 1);
+        assert_eq!(pacer.advance(Duration::ZERO), 0);
+    }
+
+    #[test_case(1_000_000; "1mhz")]
+    #[test_case(4_000; "4khz")]
+    fn test_yield_cadence(speed: u32) {
+        let period = Duration::from_secs(1) / speed;
+        let mut pacer = FramePacer::new(YIELD_PERIOD);
+        let yields: Vec<u32> = (1..=speed / 100)
+            .filter(|_| pacer.present(period))
+            .collect();
+        // 10ms worth of steps yields every 2ms, however fast the steps come
+        let steps_per_yield = YIELD_PERIOD.as_nanos().div_ceil(period.as_nanos()) as u32;
+        assert_eq!(
+            yields,
+            (1..=5).map(|idx| idx * steps_per_yield).collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case(700., true, 875.; "faster")]
+    #[test_case(700., false, 560.; "slower")]
+    #[test_case(19_000., true, 20_000.; "max")]
+    #[test_case(1.1, false, 1.; "min")]
+    fn test_adjust_speed(speed: f64, faster: bool, expected: f64) {
+        assert_eq!(adjust_speed(speed, faster), expected);
+    }
+
+    #[test_case(700., 5000.; "speed_up")]
+    #[test_case(5000., 700.; "slow_down")]
+    fn test_rate_ramp_converges(start: f64, target: f64) {
+        let mut ramp = RateRamp::new(start, Duration::from_millis(250));
+        ramp.set_target(target);
+
+        let (low, high) = (start.min(target), start.max(target));
+        let mut previous = start;
+        for _ in 0..1000 {
+            let current = ramp.advance(Duration::from_millis(10));
+            // We should move monotonically towards the target, without overshooting it
+            assert!((target - current).abs() <= (target - previous).abs());
+            assert!(low <= current && current <= high);
+            previous = current;
+        }
+
+        assert!(ramp.settled());
+        assert_eq!(ramp.current(), target);
+    }
+
+    #[test]
+    fn test_rate_ramp_partial_progress() {
+        let mut ramp = RateRamp::new(1000., Duration::from_millis(100));
+        ramp.set_target(2000.);
+        // One time constant in, we should be ~63% of the way there
+        let current = ramp.advance(Duration::from_millis(100));
+        assert!((current - 1632.).abs() < 1.);
+        assert!(!ramp.settled());
+    }
+
+    #[test]
+    fn test_rate_ramp_disabled() {
+        let mut ramp = RateRamp::new(700., Duration::ZERO);
+        ramp.set_target(1400.);
+        assert_eq!(ramp.advance(Duration::from_micros(1)), 1400.);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{
+    disasm,
+    emulator::{self, Address, Chip8State, EmulatedChip8, KeyInput},
+};
+use byteorder::{BigEndian, ByteOrder};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// The machine state right before an instruction runs, as one line of an instruction trace.
+/// Traces are plain text, one entry per line, made up of hex fields separated by spaces: the PC,
+/// the opcode there, I, and then V0 to VF. For example:
+///
+/// `0202 7002 0000 01 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00`
+///
+/// This is simple enough to produce from most other emulators, so their traces can be used as a
+/// reference to compare against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceEntry {
+    pub pc: Address,
+    pub opcode: u16,
+    pub index_register: Address,
+    pub registers: [u8; 16],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("line {line} of the trace is invalid: {reason}")]
+    InvalidLine { line: usize, reason: String },
+}
+
+impl TraceEntry {
+    /// Records the instruction `state` is about to run
+    pub fn capture(state: &Chip8State) -> TraceEntry {
+        let pc = usize::from(state.pc.0);
+        let opcode = state
+            .memory_slice(pc..pc + 2)
+            .map(BigEndian::read_u16)
+            .unwrap_or(0);
+        TraceEntry {
+            pc: state.pc,
+            opcode,
+            index_register: state.index_register,
+            registers: state.gp_registers.map(|register| register.0),
+        }
+    }
+
+    /// The instruction in assembly form, see [`disasm::mnemonic`]
+    pub fn mnemonic(&self) -> String {
+        disasm::mnemonic(self.opcode)
+    }
+
+    /// Names of the fields that differ between the two entries
+    fn differences(&self, other: &TraceEntry) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.pc != other.pc {
+            differences.push("PC".to_string());
+        }
+        if self.opcode != other.opcode {
+            differences.push("opcode".to_string());
+        }
+        if self.index_register != other.index_register {
+            differences.push("I".to_string());
+        }
+        for (idx, (ours, theirs)) in self.registers.iter().zip(other.registers).enumerate() {
+            if *ours != theirs {
+                differences.push(format!("V{idx:X}"));
+            }
+        }
+        differences
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04x} {:04x} {:04x}",
+            self.pc.0, self.opcode, self.index_register.0
+        )?;
+        for register in self.registers {
+            write!(f, " {register:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TraceEntry {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<TraceEntry, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 19 {
+            return Err(format!("expected 19 fields, found {}", fields.len()));
+        }
+        let parse_u16 = |field: &str| {
+            u16::from_str_radix(field, 16).map_err(|_| format!("{field} is not a hex number"))
+        };
+        let mut registers = [0; 16];
+        for (register, field) in registers.iter_mut().zip(&fields[3..]) {
+            *register =
+                u8::from_str_radix(field, 16).map_err(|_| format!("{field} is not a hex byte"))?;
+        }
+        Ok(TraceEntry {
+            pc: Address(parse_u16(fields[0])?),
+            opcode: parse_u16(fields[1])?,
+            index_register: Address(parse_u16(fields[2])?),
+            registers,
+        })
+    }
+}
+
+/// Parses a whole trace, skipping blank lines and comments starting with `#`
+pub fn parse_trace(trace: &str) -> Result<Vec<TraceEntry>, Error> {
+    trace
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(idx, line)| {
+            line.parse().map_err(|reason| Error::InvalidLine {
+                line: idx + 1,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// The first point where a run stopped matching a reference trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the instruction in the trace
+    pub step: usize,
+    pub expected: TraceEntry,
+    pub actual: TraceEntry,
+    /// The instruction before, which most likely caused the divergence
+    pub previous: Option<TraceEntry>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "diverged from the reference before instruction {} (differs in {})",
+            self.step,
+            self.expected.differences(&self.actual).join(", ")
+        )?;
+        if let Some(previous) = &self.previous {
+            writeln!(f, "  previous: {previous}")?;
+        }
+        writeln!(f, "  expected: {}", self.expected)?;
+        writeln!(f, "  actual:   {}", self.actual)
+    }
+}
+
+/// Runs `chip` one instruction at a time (each taking `period`, with no keys pressed) while
+/// comparing its state against `reference` before every instruction. Returns the first
+/// divergence, or `None` if the run matched the whole reference.
+pub fn diff_trace(
+    chip: &mut EmulatedChip8,
+    reference: &[TraceEntry],
+    period: Duration,
+) -> emulator::Result<Option<Divergence>> {
+    let mut previous = None;
+    for (step, expected) in reference.iter().enumerate() {
+        let actual = TraceEntry::capture(chip.get_state());
+        if actual != *expected {
+            return Ok(Some(Divergence {
+                step,
+                expected: *expected,
+                actual,
+                previous,
+            }));
+        }
+        chip.step(KeyInput::default(), period)?;
+        previous = Some(actual);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_trace, parse_trace, Error, TraceEntry};
+    use crate::{
+        emulator::{Address, EmulatedChip8, KeyInput},
+        program::Program,
+    };
+    use expect_test::expect;
+    use std::time::Duration;
+
+    /// LD V0, 0x01; ADD V0, 0x02; LD I, 0x300; LD V1, 0x05
+    fn program() -> Program {
+        Program::new_from_data(&[0x60, 0x01, 0x70, 0x02, 0xA3, 0x00, 0x61, 0x05]).unwrap()
+    }
+
+    fn record_trace(steps: usize) -> Vec<TraceEntry> {
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program());
+        (0..steps)
+            .map(|_| {
+                let entry = TraceEntry::capture(chip.get_state());
+                chip.step(KeyInput::default(), Duration::ZERO).unwrap();
+                entry
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_trace_round_trip() {
+        let trace = record_trace(4);
+        let text: String = trace.iter().map(|entry| format!("{entry}\n")).collect();
+        assert_eq!(parse_trace(&format!("# header\n\n{text}")).unwrap(), trace);
+
+        assert_eq!(
+            parse_trace("0200 6001 0000 00"),
+            Err(Error::InvalidLine {
+                line: 1,
+                reason: "expected 19 fields, found 4".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_trace_matches() {
+        let reference = record_trace(4);
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program());
+        assert_eq!(
+            diff_trace(&mut chip, &reference, Duration::ZERO).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diff_trace_divergence() {
+        // Pretend the reference emulator's ADD gave a different result
+        let mut reference = record_trace(4);
+        for entry in &mut reference[2..] {
+            entry.registers[0] = 0x04;
+        }
+        let mut chip = EmulatedChip8::new();
+        chip.load_program(&program());
+
+        let divergence = diff_trace(&mut chip, &reference, Duration::ZERO)
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(divergence.actual.pc, Address(0x204));
+        expect![[r#"
+            diverged from the reference before instruction 2 (differs in V0)
+              previous: 0202 7002 0000 01 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+              expected: 0204 a300 0000 04 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+              actual:   0204 a300 0000 03 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+        "#]]
+        .assert_eq(&divergence.to_string());
+    }
+}
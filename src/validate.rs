@@ -0,0 +1,280 @@
+use crate::{
+    emulator::Address,
+    opcodes::{self, OpCodeData},
+    program::{Program, PROGRAM_START},
+};
+use byteorder::{BigEndian, ByteOrder};
+use std::{collections::HashSet, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A potential problem found while statically checking a ROM
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Issue {
+    /// ROMs are made of 2-byte instructions, so an odd length usually means truncation
+    OddLength(usize),
+    /// The first instruction at the program start isn't one we recognise
+    UnrecognisedEntry(u16),
+    /// The program starts with `0x0000`, so it's most likely data, zero padding or a ROM meant to
+    /// be loaded at a different address
+    BlankEntry,
+    /// A reachable instruction isn't one we recognise (it may also be data we wrongly followed)
+    UnsupportedOpcode { address: Address, opcode: u16 },
+    /// A jump or call lands outside of the loaded program
+    JumpOutOfRange { address: Address, target: Address },
+    /// A jump or call lands on an odd address, so between two instructions
+    MisalignedJump { address: Address, target: Address },
+    /// The instruction behaves differently between interpreters, so may need a quirk setting
+    QuirkSensitive { address: Address, opcode: u16 },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::UnrecognisedEntry(_) | Issue::BlankEntry | Issue::JumpOutOfRange { .. } => {
+                Severity::Error
+            }
+            Issue::OddLength(_)
+            | Issue::UnsupportedOpcode { .. }
+            | Issue::MisalignedJump { .. } => Severity::Warning,
+            Issue::QuirkSensitive { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::OddLength(len) => write!(f, "ROM has an odd length of {len} bytes"),
+            Issue::UnrecognisedEntry(opcode) => write!(
+                f,
+                "entry point holds {opcode:#06x}, which isn't a recognised instruction"
+            ),
+            Issue::BlankEntry => write!(
+                f,
+                "entry point holds 0x0000, which looks like data rather than code; the ROM may be \
+                 corrupt or need a different load address"
+            ),
+            Issue::UnsupportedOpcode { address, opcode } => {
+                write!(f, "{address}: unsupported opcode {opcode:#06x}")
+            }
+            Issue::JumpOutOfRange { address, target } => {
+                write!(f, "{address}: jumps to {target}, outside of the program")
+            }
+            Issue::MisalignedJump { address, target } => {
+                write!(f, "{address}: jumps to odd address {target}")
+            }
+            Issue::QuirkSensitive { address, opcode } => write!(
+                f,
+                "{address}: {opcode:#06x} behaves differently between interpreters"
+            ),
+        }
+    }
+}
+
+/// Everything found while validating a ROM
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    /// True if there's any issue that would almost certainly break the ROM
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity() == Severity::Error)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "no issues found");
+        }
+        for issue in &self.issues {
+            writeln!(f, "[{:?}] {issue}", issue.severity())?;
+        }
+        Ok(())
+    }
+}
+
+/// Statically checks a ROM for common problems, without running it. Rather than treating every
+/// word as an instruction (ROMs mix code and sprite data freely) we follow the control flow from
+/// the entry point, so only reachable instructions get checked.
+pub fn validate(program: &Program) -> Report {
+    let data = program.data();
+    let start = usize::from(PROGRAM_START);
+    let end = start + data.len();
+    let instructions = opcodes::supported_instructions();
+    let mut report = Report::default();
+
+    if !data.len().is_multiple_of(2) {
+        report.issues.push(Issue::OddLength(data.len()));
+    }
+
+    let mut to_visit = vec![start];
+    let mut visited = HashSet::new();
+    while let Some(pc) = to_visit.pop() {
+        // Jumps outside the program get flagged where the jump happens
+        if pc + 2 > end || !visited.insert(pc) {
+            continue;
+        }
+        let address = Address(pc as u16);
+        let opcode = BigEndian::read_u16(&data[pc - start..]);
+        let opcode_data = OpCodeData::decode(opcode);
+
+        if !opcodes::is_supported(&instructions, opcode) {
+            report.issues.push(if pc == start {
+                entry_issue(opcode)
+            } else {
+                Issue::UnsupportedOpcode { address, opcode }
+            });
+            continue;
+        }
+
+        if is_quirk_sensitive(opcode) {
+            report
+                .issues
+                .push(Issue::QuirkSensitive { address, opcode });
+        }
+
+        let next = pc + 2;
+        match opcode & 0xF000 {
+            // Jump
+            0x1000 => to_visit.extend(check_target(&mut report, address, opcode_data.nnn, end)),
+            // Call, which eventually comes back
+            0x2000 => {
+                to_visit.extend(check_target(&mut report, address, opcode_data.nnn, end));
+                to_visit.push(next);
+            }
+            // Conditional skips
+            0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 => to_visit.extend([next, next + 2]),
+            // Jump with offset, which we can't follow without knowing V0
+            0xB000 => {}
+            // Return
+            _ if opcode == 0x00EE => {}
+            _ => to_visit.push(next),
+        }
+    }
+
+    report
+}
+
+/// Checks just the first instruction of a ROM, which is cheap enough to do every time a program
+/// gets loaded. Returns the same issue [`validate`] would report for the entry point, if any.
+pub fn check_entry(program: &Program) -> Option<Issue> {
+    let data = program.data();
+    if data.len() < 2 {
+        return None;
+    }
+    let opcode = BigEndian::read_u16(data);
+    (!opcodes::is_supported(&opcodes::supported_instructions(), opcode))
+        .then(|| entry_issue(opcode))
+}
+
+fn entry_issue(opcode: u16) -> Issue {
+    if opcode == 0x0000 {
+        Issue::BlankEntry
+    } else {
+        Issue::UnrecognisedEntry(opcode)
+    }
+}
+
+fn check_target(report: &mut Report, address: Address, target: u16, end: usize) -> Option<usize> {
+    let target_address = Address(target);
+    let target = usize::from(target);
+    if target < usize::from(PROGRAM_START) || target >= end {
+        report.issues.push(Issue::JumpOutOfRange {
+            address,
+            target: target_address,
+        });
+        return None;
+    }
+    if !target.is_multiple_of(2) {
+        report.issues.push(Issue::MisalignedJump {
+            address,
+            target: target_address,
+        });
+    }
+    Some(target)
+}
+
+/// Shifts, `FX55`/`FX65`, `BNNN` and the logical operations all have well known differences
+/// between the COSMAC VIP, SUPER-CHIP and modern interpreters
+fn is_quirk_sensitive(opcode: u16) -> bool {
+    matches!(opcode & 0xF00F, 0x8001 | 0x8002 | 0x8003 | 0x8006 | 0x800E)
+        || matches!(opcode & 0xF0FF, 0xF055 | 0xF065)
+        || opcode & 0xF000 == 0xB000
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_entry, validate, Issue};
+    use crate::{emulator::Address, program::Program};
+
+    #[test]
+    fn test_validate_jump_past_program() {
+        // LD V0, 0x01; JP 0xF00
+        let program = Program::new_from_data(&[0x60, 0x01, 0x1F, 0x00]).unwrap();
+        let report = validate(&program);
+        assert_eq!(
+            report.issues,
+            vec![Issue::JumpOutOfRange {
+                address: Address(0x202),
+                target: Address(0xF00),
+            }]
+        );
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn test_validate_clean_program() {
+        // CLS; LD V1, 0x06; SHR V1; JP 0x202
+        let program =
+            Program::new_from_data(&[0x00, 0xE0, 0x61, 0x06, 0x81, 0x06, 0x12, 0x02]).unwrap();
+        let report = validate(&program);
+        assert_eq!(
+            report.issues,
+            vec![Issue::QuirkSensitive {
+                address: Address(0x204),
+                opcode: 0x8106,
+            }]
+        );
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn test_validate_ignores_unreachable_data() {
+        // JP 0x204; <data that would be an out of range jump>; JP 0x204
+        let program = Program::new_from_data(&[0x12, 0x04, 0x1F, 0xFF, 0x12, 0x04]).unwrap();
+        assert!(validate(&program).issues.is_empty());
+    }
+
+    #[test]
+    fn test_validate_bad_entry_and_odd_length() {
+        let program = Program::new_from_data(&[0x00, 0x00, 0xAB]).unwrap();
+        assert_eq!(
+            validate(&program).issues,
+            vec![Issue::OddLength(3), Issue::BlankEntry]
+        );
+    }
+
+    #[test]
+    fn test_check_entry_blank() {
+        let program = Program::new_from_data(&[0x00, 0x00, 0x60, 0x01]).unwrap();
+        assert_eq!(check_entry(&program), Some(Issue::BlankEntry));
+        assert_eq!(validate(&program).issues, vec![Issue::BlankEntry]);
+    }
+
+    #[test]
+    fn test_check_entry_valid() {
+        let program = Program::new_from_data(&[0x60, 0x01]).unwrap();
+        assert_eq!(check_entry(&program), None);
+    }
+}